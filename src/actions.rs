@@ -0,0 +1,77 @@
+#![cfg(feature = "ssr")]
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use sqlx::{query, Row};
+use std::env;
+
+use crate::{
+    db::{save_submission, POOL},
+    schedule::week_of,
+};
+
+/// An ntfy http action calling `path` on this server, `None` if `BASE_URL` isn't set.
+pub fn action(label: &str, path: &str) -> Option<String> {
+    let base = env::var("BASE_URL").ok()?;
+    Some(format!(
+        "http, {}, {}{}, method=POST, clear=true",
+        label,
+        base.trim_end_matches('/'),
+        path
+    ))
+}
+
+/// An ntfy view action opening `path` on this server, `None` if `BASE_URL` isn't set.
+pub fn view(label: &str, path: &str) -> Option<String> {
+    let base = env::var("BASE_URL").ok()?;
+    Some(format!("view, {}, {}{}", label, base.trim_end_matches('/'), path))
+}
+
+/// An ntfy view action opening `SUBMIT_URL`, `None` if it isn't set.
+pub fn submit_link() -> Option<String> {
+    let url = env::var("SUBMIT_URL").ok()?;
+    Some(format!("view, Submit, {}", url))
+}
+
+/// Serves `/actions/ship/{token}`, the "Shipped!" button of deadline alerts.
+pub async fn ship(Path(token): Path<String>) -> Response {
+    let username: String = match query("SELECT username FROM users WHERE action_token = ? AND action_token != '' AND disabled = 0")
+        .bind(token)
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r.get("username"),
+        Ok(None) => return (StatusCode::NOT_FOUND, "Unknown user").into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    match save_submission(&username, week_of(Utc::now().naive_utc()), "").await {
+        Ok(_) => "Marked as shipped".into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// Serves `/actions/milestone/{token}/done`, the "Mark done" button of milestone reminders.
+pub async fn milestone_done(Path(token): Path<String>) -> Response {
+    match query("UPDATE milestones SET done = 1 WHERE token = ? AND token != '' AND username IN (SELECT username FROM users WHERE disabled = 0)")
+        .bind(token)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(r) if r.rows_affected() > 0 => "Marked as done".into_response(),
+        Ok(_) => (StatusCode::NOT_FOUND, "Unknown milestone").into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
@@ -0,0 +1,246 @@
+#![cfg(feature = "ssr")]
+
+use chrono::{Datelike, Days, Duration, Utc};
+use sqlx::{query, sqlite::SqliteRow, Row};
+
+use crate::{
+    auth::topic_error,
+    datatypes::Role,
+    db::{
+        day_override, delete_user, ntfy_topic, set_disabled, set_ntfy_topic, set_role,
+        settings_from_row, tracked_projects, POOL,
+    },
+    notifications::{deliver, evaluate, week_minute, Reminder},
+    schedule::WEEK,
+};
+
+pub const USAGE: &str = "Usage: siege-reminder admin <command>
+
+Commands:
+  users [search]             Users whose name or email contains the search
+  schedule <user>            Reminder schedule, projects and where notifications go
+  disable <user>             Stop notifications and logins, ends all sessions
+  enable <user>              Undo disable
+  role <user> <admin|user>   Let a user see /admin or take that away
+  topic <user> <topic>       Set the secret ntfy topic login codes and notifications go to
+  delete <user> [--yes]      Remove everything stored about the user
+  remind <user> [day]        Send the reminder of a day now, today by default, without
+                             recording it
+  evaluate <user> [day]      Show that reminder without sending or storing it
+  vacuum                     Compact db.sqlite
+  migrate                    Bring db.sqlite up to the current schema";
+
+const DAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Runs `siege-reminder admin ...` against `db.sqlite`, the schema is already migrated.
+pub async fn run(args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["users"] => users("").await,
+        ["users", search] => users(search).await,
+        ["schedule", user] => schedule(user).await,
+        ["disable", user] => disable(user, true).await,
+        ["enable", user] => disable(user, false).await,
+        ["role", user, role] => {
+            let role = match *role {
+                "admin" => Role::Admin,
+                "user" => Role::User,
+                _ => return Err(format!("Roles are admin and user, not {}", role)),
+            };
+            match set_role(user, role).await {
+                Ok(true) => println!("{} is {:?} now", user, role),
+                Ok(false) => return Err(format!("There's no user {}", user)),
+                Err(e) => return Err(e.to_string()),
+            }
+            Ok(())
+        }
+        ["topic", user, topic] => {
+            if let Some(e) = topic_error(user, topic) {
+                return Err(e.to_string());
+            }
+            match set_ntfy_topic(user, topic).await {
+                Ok(true) => println!("Notifications of {} go to {} now", user, topic),
+                Ok(false) => return Err(format!("There's no user {}", user)),
+                Err(e) => return Err(e.to_string()),
+            }
+            Ok(())
+        }
+        ["delete", user] => {
+            user_row(user).await?;
+            println!(
+                "This deletes everything about {}, run again with --yes",
+                user
+            );
+            Ok(())
+        }
+        ["delete", user, "--yes"] => {
+            let rows = delete_user(user).await.map_err(|e| e.to_string())?;
+            println!("Deleted {} rows of {}", rows, user);
+            Ok(())
+        }
+        ["remind", user] => remind(user, None, true).await,
+        ["remind", user, day] => remind(user, Some(day), true).await,
+        ["evaluate", user] => remind(user, None, false).await,
+        ["evaluate", user, day] => remind(user, Some(day), false).await,
+        ["vacuum"] => {
+            query("VACUUM")
+                .execute(&*POOL)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("Vacuumed db.sqlite");
+            Ok(())
+        }
+        ["help"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        ["migrate"] => {
+            println!("db.sqlite is up to date");
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+async fn user_row(username: &str) -> Result<SqliteRow, String> {
+    match query("SELECT * FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => Ok(r),
+        Ok(None) => Err(format!("There's no user {}", username)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn users(search: &str) -> Result<(), String> {
+    let rows = query(
+        "SELECT username, email, disabled, (SELECT MAX(time) FROM evaluations e WHERE e.username = u.username) AS last
+        FROM users u WHERE username LIKE ? OR email LIKE ? ORDER BY username",
+    )
+    .bind(format!("%{}%", search))
+    .bind(format!("%{}%", search))
+    .fetch_all(&*POOL)
+    .await
+    .map_err(|e| e.to_string())?;
+    for r in &rows {
+        println!(
+            "{:<24} {:<32} last reminder {}{}",
+            r.get::<String, _>("username"),
+            r.get::<String, _>("email"),
+            r.get::<Option<String>, _>("last")
+                .as_deref()
+                .and_then(|t| t.get(..16))
+                .unwrap_or("never"),
+            if r.get::<i64, _>("disabled") != 0 {
+                ", disabled"
+            } else {
+                ""
+            }
+        );
+    }
+    println!("{} users", rows.len());
+    Ok(())
+}
+
+async fn schedule(username: &str) -> Result<(), String> {
+    let row = user_row(username).await?;
+    let settings = settings_from_row(&row).map_err(|e| e.to_string())?;
+    let time = |minute: i64| format!("{:02}:{:02}", minute / 60 % 24, minute % 60);
+    println!("UTC offset {}h", settings.offset as f64 / 3600.0);
+    for (day, slot) in DAYS.iter().zip(settings.slots()) {
+        println!(
+            "{:<10} {} UTC, {} local, goal {}h, {:?}",
+            day,
+            time(slot.time.rem_euclid(WEEK)),
+            time((slot.time + settings.offset as i64 / 60).rem_euclid(WEEK)),
+            slot.goal,
+            slot.window
+        );
+    }
+    let projects = tracked_projects(username)
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("Tracking {}", projects.join(", "));
+    println!(
+        "Notifications go to ntfy topic {}",
+        ntfy_topic(username).await.map_err(|e| e.to_string())?
+    );
+    if row.get::<i64, _>("disabled") != 0 {
+        println!("Disabled");
+    }
+    Ok(())
+}
+
+async fn disable(username: &str, disabled: bool) -> Result<(), String> {
+    match set_disabled(username, disabled).await {
+        Ok(true) if disabled => println!("Disabled {}", username),
+        Ok(true) => println!("Enabled {}", username),
+        Ok(false) => return Err(format!("There's no user {}", username)),
+        Err(e) => return Err(e.to_string()),
+    }
+    Ok(())
+}
+
+/// Builds the reminder of `day` this week as the scheduler would right now, with the days override,
+/// and sends it, or only prints it if `send` is false. Sending only notifies, the evaluation and
+/// carry stay as the scheduler left them.
+async fn remind(username: &str, day: Option<&str>, send: bool) -> Result<(), String> {
+    let row = user_row(username).await?;
+    let settings = settings_from_row(&row).map_err(|e| e.to_string())?;
+    let now = Utc::now().naive_utc();
+    let day = match day {
+        Some(d) => DAYS
+            .iter()
+            .position(|name| d.len() >= 2 && name.starts_with(&d.to_lowercase()))
+            .ok_or(format!("Unknown day {}", d))?,
+        None => settings.local_date(now).weekday().num_days_from_monday() as usize,
+    };
+    let (monday, time_passed) = week_minute(now);
+    let today = settings.local_date(now);
+    let date =
+        today - Days::new(today.weekday().num_days_from_monday() as u64) + Days::new(day as u64);
+    let settings = match day_override(username, date)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Some(o) => {
+            let time = date.and_hms_opt(0, 0, 0).unwrap()
+                - Duration::seconds(settings.offset as i64)
+                + Duration::minutes(o.time as i64);
+            println!("{} has an override", date);
+            settings.with_slot(day, (time - monday).num_minutes(), o.goal)
+        }
+        None => settings,
+    };
+    let reminder = evaluate(username, &row, settings, day, monday, time_passed).await?;
+    print_reminder(&reminder);
+    if send && deliver(username, reminder, false).await {
+        println!("Sent");
+    } else if send {
+        return Err("Not everything could be sent, see above".to_string());
+    }
+    Ok(())
+}
+
+fn print_reminder(reminder: &Reminder) {
+    println!("{} reminder at {}", DAYS[reminder.day], reminder.at);
+    if let Some((start, target, seconds)) = reminder.result {
+        println!("Since {}: {}s of {}s", start, seconds, target);
+    }
+    if let Some((balance, bank)) = reminder.carry {
+        println!("Balance {}s, bank {}s", balance, bank);
+    }
+    for (title, tags, msg) in &reminder.notifications {
+        println!("\n{} [{}]\n{}", title, tags.as_deref().unwrap_or(""), msg);
+    }
+}
@@ -0,0 +1,281 @@
+#![cfg(feature = "ssr")]
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{NaiveDate, Utc};
+use leptos::prelude::ServerFnError;
+use serde_json::json;
+use sqlx::{query, sqlite::SqliteRow, Row};
+use std::collections::HashMap;
+
+use crate::{
+    auth::hash_token,
+    datatypes::{ApiScope, Channels, LongGoal, Settings},
+    db::{disabled, long_goal_from_row, ntfy_topic, settings_from_row, POOL},
+    notifications::send,
+    server::{
+        progress_for, projects_for, save_channels, save_long_goal, save_projects, save_settings,
+        week_for,
+    },
+};
+
+/// The `/api/v1` routes. Every request needs an `Authorization: Bearer <token>` header with a
+/// personal API token, changes need a read-write one.
+pub fn router<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/progress", get(progress))
+        .route("/schedule", get(schedule).put(set_schedule))
+        .route("/goal", get(goal).put(set_goal))
+        .route("/projects", get(projects).put(set_projects))
+        .route("/channels", get(channels).put(set_channels))
+        .route("/week", get(week))
+        .route("/test", post(test))
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// Turns the errors of the shared server function code into API errors.
+fn server_error(e: ServerFnError) -> Response {
+    match e {
+        ServerFnError::ServerError(e) if e == "Database error" => {
+            error(StatusCode::INTERNAL_SERVER_ERROR, &e)
+        }
+        ServerFnError::ServerError(e) => error(StatusCode::BAD_REQUEST, &e),
+        e => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+/// The user of the requests API token, if it has `scope`.
+async fn authorize(headers: &HeaderMap, scope: ApiScope) -> Result<String, Response> {
+    let token = match headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+    {
+        Some(t) => t.trim(),
+        None => return Err(error(StatusCode::UNAUTHORIZED, "Missing API token")),
+    };
+    let row = match query("SELECT * FROM api_tokens WHERE hash = ?")
+        .bind(hash_token(token))
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(error(StatusCode::UNAUTHORIZED, "Unknown API token")),
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Database error"));
+        }
+    };
+    let username: String = row.get("username");
+    match disabled(&username).await {
+        Ok(false) => (),
+        Ok(true) => return Err(error(StatusCode::FORBIDDEN, "This account is disabled")),
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Database error"));
+        }
+    }
+    if scope == ApiScope::ReadWrite && ApiScope::from_i64(row.get("scope")) == ApiScope::Read {
+        return Err(error(StatusCode::FORBIDDEN, "This token can only read"));
+    }
+    if let Err(e) = query("UPDATE api_tokens SET last_used = ? WHERE id = ?")
+        .bind(Utc::now().naive_utc().to_string())
+        .bind(row.get::<i64, _>("id"))
+        .execute(&*POOL)
+        .await
+    {
+        eprintln!("{}", e);
+    }
+    Ok(username)
+}
+
+async fn user_row(username: &str) -> Result<SqliteRow, Response> {
+    match query("SELECT * FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => Ok(r),
+        Ok(None) => Err(error(StatusCode::NOT_FOUND, "Save your settings first")),
+        Err(e) => {
+            eprintln!("{}", e);
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, "Database error"))
+        }
+    }
+}
+
+/// `GET /api/v1/progress`, time on the tracked projects this week and today.
+async fn progress(headers: HeaderMap) -> Response {
+    let username = match authorize(&headers, ApiScope::Read).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    match progress_for(&username).await {
+        Ok(p) => Json(p).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `GET /api/v1/schedule`, reminder times are minutes since monday 00:00 UTC.
+async fn schedule(headers: HeaderMap) -> Response {
+    let username = match authorize(&headers, ApiScope::Read).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    let row = match user_row(&username).await {
+        Ok(r) => r,
+        Err(r) => return r,
+    };
+    match settings_from_row(&row) {
+        Ok(s) => Json(s).into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+/// `PUT /api/v1/schedule`, takes the same JSON as `GET` returns.
+async fn set_schedule(headers: HeaderMap, Json(settings): Json<Settings>) -> Response {
+    let username = match authorize(&headers, ApiScope::ReadWrite).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    match save_settings(&username, &settings).await {
+        Ok(_) => Json(settings).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `GET /api/v1/goal`, the event goal or `null`.
+async fn goal(headers: HeaderMap) -> Response {
+    let username = match authorize(&headers, ApiScope::Read).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    let row = match user_row(&username).await {
+        Ok(r) => r,
+        Err(r) => return r,
+    };
+    match long_goal_from_row(&row) {
+        Ok(g) => Json(g).into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+/// `PUT /api/v1/goal`, `null` removes it.
+async fn set_goal(headers: HeaderMap, Json(goal): Json<Option<LongGoal>>) -> Response {
+    let username = match authorize(&headers, ApiScope::ReadWrite).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    match save_long_goal(&username, goal.clone()).await {
+        Ok(_) => Json(goal).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `GET /api/v1/projects`, this weeks Hackatime projects and the tracked ones.
+async fn projects(headers: HeaderMap) -> Response {
+    let username = match authorize(&headers, ApiScope::Read).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    match projects_for(&username).await {
+        Ok(p) => Json(p).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `PUT /api/v1/projects`, takes the list of projects to track.
+async fn set_projects(headers: HeaderMap, Json(tracked): Json<Vec<String>>) -> Response {
+    let username = match authorize(&headers, ApiScope::ReadWrite).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    match save_projects(&username, &tracked).await {
+        Ok(_) => Json(tracked).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `GET /api/v1/channels`, where notifications go.
+async fn channels(headers: HeaderMap) -> Response {
+    let username = match authorize(&headers, ApiScope::Read).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    match user_row(&username).await {
+        Ok(r) => Json(Channels {
+            ntfy_topic: r.get("ntfy_topic"),
+        })
+        .into_response(),
+        Err(r) => r,
+    }
+}
+
+/// `PUT /api/v1/channels`, only accounts that already have a secret topic can change it.
+async fn set_channels(headers: HeaderMap, Json(channels): Json<Channels>) -> Response {
+    let username = match authorize(&headers, ApiScope::ReadWrite).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    let channels = Channels {
+        ntfy_topic: channels.ntfy_topic.trim().to_string(),
+    };
+    match save_channels(&username, channels.clone()).await {
+        Ok(_) => Json(channels).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `GET /api/v1/week?week=2025-09-08`, projects and reminder results of the week the date is in,
+/// the current one without a date.
+async fn week(headers: HeaderMap, Query(params): Query<HashMap<String, String>>) -> Response {
+    let username = match authorize(&headers, ApiScope::Read).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    let day = match params.get("week").map(|w| w.parse::<NaiveDate>()) {
+        Some(Ok(d)) => d,
+        Some(Err(_)) => return error(StatusCode::BAD_REQUEST, "Dates look like 2025-09-08"),
+        None => Utc::now().date_naive(),
+    };
+    match week_for(&username, day).await {
+        Ok(w) => Json(w).into_response(),
+        Err(e) => server_error(e),
+    }
+}
+
+/// `POST /api/v1/test`, sends a test notification and returns the topic it went to.
+async fn test(headers: HeaderMap) -> Response {
+    let username = match authorize(&headers, ApiScope::ReadWrite).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    send(
+        &username,
+        "Test notification",
+        Some("bell"),
+        "Your reminders will show up like this.",
+    )
+    .await;
+    match ntfy_topic(&username).await {
+        Ok(topic) => Json(json!({ "topic": topic })).into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
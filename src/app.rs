@@ -2,10 +2,10 @@ use leptos::prelude::*;
 use leptos_meta::{provide_meta_context, MetaTags, Title};
 use leptos_router::{
     components::{Route, Router, Routes},
-    StaticSegment,
+    SsrMode, StaticSegment,
 };
 
-use crate::pages::HomePage;
+use crate::pages::{AdminPage, CalendarPage, HomePage, JournalPage, StatsPage};
 
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     view! {
@@ -49,6 +49,11 @@ pub fn App() -> impl IntoView {
             <main class="h-screen w-screen grid grid-cols-3 bg-zinc-800 text-zinc-200">
                 <Routes fallback=|| "Page not found.".into_view()>
                     <Route path=StaticSegment("") view=HomePage />
+                    <Route path=StaticSegment("calendar") view=CalendarPage />
+                    <Route path=StaticSegment("journal") view=JournalPage />
+                    // rendered with its data so the charts work without javascript
+                    <Route path=StaticSegment("stats") view=StatsPage ssr=SsrMode::Async />
+                    <Route path=StaticSegment("admin") view=AdminPage />
                 </Routes>
             </main>
         </Router>
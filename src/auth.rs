@@ -0,0 +1,362 @@
+#![cfg(feature = "ssr")]
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use chrono::{Duration, NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use leptos::prelude::{expect_context, ServerFnError};
+use leptos_axum::{extract, ResponseOptions};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::{query, Row};
+use std::{env, sync::LazyLock};
+
+use crate::{
+    datatypes::Role,
+    db::{disabled, new_token, role, POOL},
+};
+
+/// Minutes a login code stays valid.
+pub const CODE_MINUTES: i64 = 10;
+/// Minutes in which a username gets at most `CODE_REQUESTS` codes and `CODE_ATTEMPTS` guesses.
+pub const CODE_WINDOW_MINUTES: i64 = 60;
+/// Codes a username can ask for in the window.
+pub const CODE_REQUESTS: i64 = 5;
+/// Wrong guesses in the window, new codes don't give more.
+pub const CODE_ATTEMPTS: i64 = 5;
+/// Sessions end after this many days without being used.
+const SESSION_DAYS: i64 = 30;
+const COOKIE: &str = "session";
+
+/// Key for the cookie signatures, sessions don't survive a restart without `SESSION_SECRET`.
+static SECRET: LazyLock<Vec<u8>> = LazyLock::new(|| match env::var("SESSION_SECRET") {
+    Ok(s) if !s.is_empty() => s.into_bytes(),
+    _ => {
+        eprintln!("SESSION_SECRET isn't set, everyone gets logged out on restarts");
+        new_token().into_bytes()
+    }
+});
+
+/// Hex sha256 of an API token, tokens are only stored like this.
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Why `topic` can't take login codes for `username`, ntfy topics are letters, digits, - and _
+/// and the username is the public default anyone can subscribe to.
+pub fn topic_error(username: &str, topic: &str) -> Option<&'static str> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if topic.is_empty() {
+        Some("Pick a secret ntfy topic, login codes go there")
+    } else if topic.len() > 64 || !topic.chars().all(allowed) {
+        Some("Topics are up to 64 letters, digits, - and _")
+    } else if topic.eq_ignore_ascii_case(username) {
+        Some("That's the default topic anyone can read, pick a secret one")
+    } else {
+        None
+    }
+}
+
+/// A six digit login code.
+pub fn new_code() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+fn mac(value: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&SECRET).expect("any key length works");
+    mac.update(value.as_bytes());
+    mac
+}
+
+/// The cookie value `{token}.{username}.{signature}`, the token links it to a row in `sessions`.
+fn cookie_value(token: &str, username: &str) -> String {
+    let value = format!("{}.{}", token, username);
+    let signature = mac(&value)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("{}.{}", value, signature)
+}
+
+/// Token and username of a cookie value, `None` if the signature doesn't match.
+fn verify(cookie: &str) -> Option<(&str, &str)> {
+    let (value, signature) = cookie.rsplit_once('.')?;
+    let signature = (0..signature.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(signature.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    mac(value).verify_slice(&signature).ok()?;
+    value.split_once('.')
+}
+
+/// A `Set-Cookie` header value, an empty value with `max_age` 0 removes the cookie.
+pub fn cookie(name: &str, value: &str, max_age: i64) -> String {
+    // only send it over https if the app is served that way
+    let secure = match env::var("BASE_URL") {
+        Ok(b) if b.starts_with("https://") => "; Secure",
+        _ => "",
+    };
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+        name, value, max_age, secure
+    )
+}
+
+/// Sends a `Set-Cookie` header with the response of a server function.
+pub fn set_cookie(cookie: &str) {
+    if let Ok(cookie) = HeaderValue::from_str(cookie) {
+        expect_context::<ResponseOptions>().append_header(header::SET_COOKIE, cookie);
+    }
+}
+
+/// Creates a session for `username`, returns the `Set-Cookie` header value for it.
+pub async fn new_session(username: &str) -> Result<String, sqlx::Error> {
+    let token = new_token();
+    let now = Utc::now().naive_utc().to_string();
+    query("INSERT INTO sessions (token, username, created, last_used) VALUES (?, ?, ?, ?)")
+        .bind(token.clone())
+        .bind(username)
+        .bind(now.clone())
+        .bind(now)
+        .execute(&*POOL)
+        .await?;
+    Ok(cookie(
+        COOKIE,
+        &cookie_value(&token, username),
+        SESSION_DAYS * 24 * 60 * 60,
+    ))
+}
+
+/// Starts a session for `username` and sends its cookie with the response.
+pub async fn start_session(username: &str) -> Result<(), sqlx::Error> {
+    set_cookie(&new_session(username).await?);
+    Ok(())
+}
+
+/// Ends the session of this request, if there is one.
+pub async fn end_session() -> Result<(), ServerFnError> {
+    if let Some((token, _)) = session().await? {
+        if query("DELETE FROM sessions WHERE token = ?")
+            .bind(token)
+            .execute(&*POOL)
+            .await
+            .is_err()
+        {
+            return Err(ServerFnError::new("Database error"));
+        }
+    }
+    set_cookie(&cookie(COOKIE, "", 0));
+    Ok(())
+}
+
+/// The value of the cookie `name` in `headers`.
+pub fn cookie_from<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(';'))
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| value)
+}
+
+/// Token and username of the session cookie in `headers`, if it's signed by us.
+fn session_from(headers: &HeaderMap) -> Option<(String, String)> {
+    cookie_from(headers, COOKIE)
+        .and_then(verify)
+        .map(|(token, username)| (token.to_string(), username.to_string()))
+}
+
+/// Token and username of this requests session cookie, if it's signed by us.
+pub async fn session() -> Result<Option<(String, String)>, ServerFnError> {
+    let headers: HeaderMap = extract().await?;
+    Ok(session_from(&headers))
+}
+
+/// The user of the session cookie in `headers`, `None` if there is none or it was revoked or
+/// expired.
+pub async fn user_from(headers: &HeaderMap) -> Result<Option<String>, sqlx::Error> {
+    let (token, username) = match session_from(headers) {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    // revoked sessions still have a valid signature
+    let last_used = match query("SELECT last_used FROM sessions WHERE token = ? AND username = ?")
+        .bind(token.clone())
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await?
+    {
+        Some(r) => r.get::<String, _>("last_used"),
+        None => return Ok(None),
+    };
+    let now = Utc::now().naive_utc();
+    match NaiveDateTime::parse_from_str(&last_used, "%Y-%m-%d %H:%M:%S%.f") {
+        Ok(t) if now - t < Duration::days(SESSION_DAYS) => (),
+        _ => return Ok(None),
+    }
+    if disabled(&username).await? {
+        return Ok(None);
+    }
+    query("UPDATE sessions SET last_used = ? WHERE token = ?")
+        .bind(now.to_string())
+        .bind(token)
+        .execute(&*POOL)
+        .await?;
+    Ok(Some(username))
+}
+
+/// The logged in user, for server functions that only read.
+pub async fn current_user() -> Result<String, ServerFnError> {
+    let headers: HeaderMap = extract().await?;
+    match user_from(&headers).await {
+        Ok(Some(username)) => Ok(username),
+        Ok(None) => Err(ServerFnError::new("Log in first")),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// The logged in user for server functions that change something, other sites can't make the
+/// browser call them.
+pub async fn require_user() -> Result<String, ServerFnError> {
+    require_same_origin().await?;
+    current_user().await
+}
+
+/// Fails for requests from other sites, for server functions that change something without a
+/// logged in user.
+pub async fn require_same_origin() -> Result<(), ServerFnError> {
+    let headers: HeaderMap = extract().await?;
+    if !same_origin(&headers, env::var("BASE_URL").ok().as_deref()) {
+        return Err(ServerFnError::new("Cross-site request"));
+    }
+    Ok(())
+}
+
+/// The logged in user if they're an admin, for the admin page.
+pub async fn current_admin() -> Result<String, ServerFnError> {
+    admin(current_user().await?).await
+}
+
+/// Like `require_user`, for admins.
+pub async fn require_admin() -> Result<String, ServerFnError> {
+    admin(require_user().await?).await
+}
+
+async fn admin(username: String) -> Result<String, ServerFnError> {
+    match role(&username).await {
+        Ok(Role::Admin) => Ok(username),
+        Ok(Role::User) => Err(ServerFnError::new("Only admins can see this")),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Whether the request comes from a page of this app, by its `Origin` or else `Referer` header.
+/// Without a `base` URL it has to match the `Host` header.
+fn same_origin(headers: &HeaderMap, base: Option<&str>) -> bool {
+    let origin = match headers
+        .get(header::ORIGIN)
+        .or_else(|| headers.get(header::REFERER))
+        .and_then(|o| o.to_str().ok())
+    {
+        Some(o) => o,
+        None => return false,
+    };
+    let matches = |base: &str| {
+        let base = base.trim_end_matches('/');
+        origin == base || origin.starts_with(&format!("{}/", base))
+    };
+    match base {
+        Some(base) => matches(base),
+        None => match headers.get(header::HOST).and_then(|h| h.to_str().ok()) {
+            Some(host) => {
+                matches(&format!("http://{}", host)) || matches(&format!("https://{}", host))
+            }
+            None => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(header::HeaderName, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn topics() {
+        assert_eq!(topic_error("orpheus", "orpheus-x7Kq2"), None);
+        assert!(topic_error("orpheus", "").is_some());
+        assert!(topic_error("orpheus", "Orpheus").is_some());
+        assert!(topic_error("orpheus", "my topic").is_some());
+        assert!(topic_error("orpheus", &"a".repeat(65)).is_some());
+    }
+
+    #[test]
+    fn signed_cookies() {
+        let value = cookie_value("token", "orpheus");
+        assert_eq!(verify(&value), Some(("token", "orpheus")));
+
+        // a changed signature
+        let mut tampered = value.clone();
+        let last = if tampered.ends_with('0') { "1" } else { "0" };
+        tampered.replace_range(tampered.len() - 1.., last);
+        assert_eq!(verify(&tampered), None);
+        assert_eq!(verify(&value[..value.len() - 2]), None);
+        assert_eq!(verify("token.orpheus"), None);
+
+        // the signature of someone else
+        let tampered = value.replacen(".orpheus.", ".heidi.", 1);
+        assert_eq!(verify(&tampered), None);
+        let (_, signature) = value.rsplit_once('.').unwrap();
+        assert_eq!(verify(&format!("other.orpheus.{}", signature)), None);
+    }
+
+    #[test]
+    fn session_cookie_name() {
+        let value = cookie_value("token", "orpheus");
+        let session = |cookie: String| session_from(&headers(&[(header::COOKIE, &cookie)]));
+        assert_eq!(
+            session(format!("theme=dark; session={}", value)),
+            Some(("token".to_string(), "orpheus".to_string()))
+        );
+        assert_eq!(session(format!("other={}", value)), None);
+        assert_eq!(session(format!("xsession={}", value)), None);
+        assert_eq!(session(format!("{}={}", crate::oidc::LINK_COOKIE, value)), None);
+    }
+
+    #[test]
+    fn origins() {
+        let base = Some("https://siege.example/");
+        let from = |name: header::HeaderName, value: &str, base: Option<&str>| {
+            same_origin(&headers(&[(name, value), (header::HOST, "siege.example")]), base)
+        };
+        assert!(from(header::ORIGIN, "https://siege.example", base));
+        assert!(from(header::REFERER, "https://siege.example/calendar", base));
+        assert!(!from(header::ORIGIN, "https://siege.example.evil.com", base));
+        assert!(!from(header::ORIGIN, "http://siege.example", base));
+        assert!(!from(header::ORIGIN, "https://evil.com", base));
+        assert!(!same_origin(&headers(&[(header::HOST, "siege.example")]), base));
+
+        // without BASE_URL the host has to match
+        assert!(from(header::ORIGIN, "http://siege.example", None));
+        assert!(from(header::REFERER, "https://siege.example/", None));
+        assert!(!from(header::ORIGIN, "https://evil.com", None));
+        assert!(!from(header::ORIGIN, "https://siege.example.evil.com", None));
+        assert!(!same_origin(
+            &headers(&[(header::ORIGIN, "https://siege.example")]),
+            None
+        ));
+    }
+}
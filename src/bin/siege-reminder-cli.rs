@@ -0,0 +1,308 @@
+//! Terminal client for the `/api/v1` JSON API. Reads the server and a personal API token from
+//! `~/.config/siege-reminder/config`, or the file in `SIEGE_REMINDER_CONFIG`:
+//!
+//! ```text
+//! url = https://siege.tim.hackclub.app
+//! token = sr_...
+//! ```
+
+use std::{env, fs, path::PathBuf, process::exit};
+
+use chrono::{Duration, NaiveTime, Timelike};
+use reqwest::{Client, Method};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use siege_reminder::{
+    datatypes::{Progress, Projects, Settings, WeekData},
+    schedule::{hm, WEEK},
+};
+
+const USAGE: &str = "Usage: siege-reminder-cli <command>
+
+Commands:
+  today                          Progress against todays and this weeks goal (default)
+  projects                       This weeks Hackatime projects, tracked ones marked with *
+  track <project>...             Track these projects instead
+  slots                          The reminder schedule in your timezone
+  slot <day> <HH:MM> [goal]      Move a days reminder and optionally change its goal (hours)
+  test                           Send a test notification
+  summary [YYYY-MM-DD]           Summary of this week or the week of a date";
+
+const DAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+struct Config {
+    url: String,
+    token: String,
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = env::var("SIEGE_REMINDER_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(d) if !d.is_empty() => PathBuf::from(d),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(dir.join("siege-reminder").join("config"))
+}
+
+/// Reads `key = value` lines, `#` starts a comment.
+fn load_config() -> Result<Config, String> {
+    let path = config_path().ok_or("Can't find the config directory, set SIEGE_REMINDER_CONFIG")?;
+    let text = fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "Can't read {}: {}\nCreate it with a `token = ...` line, tokens are made in the web app.",
+            path.display(),
+            e
+        )
+    })?;
+    let mut url = "https://siege.tim.hackclub.app".to_string();
+    let mut token = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+            Some(("url", v)) => url = v.trim_end_matches('/').to_string(),
+            Some(("token", v)) => token = Some(v.to_string()),
+            _ => return Err(format!("Can't understand `{}` in {}", line, path.display())),
+        }
+    }
+    match token {
+        Some(token) => Ok(Config { url, token }),
+        None => Err(format!("No `token = ...` in {}", path.display())),
+    }
+}
+
+struct Api {
+    client: Client,
+    config: Config,
+}
+
+impl Api {
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<T, String> {
+        let mut request = self
+            .client
+            .request(method, format!("{}/api/v1{}", self.config.url, path))
+            .bearer_auth(&self.config.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        let json: Value = response.json().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(match json.get("error").and_then(|e| e.as_str()) {
+                Some(e) => e.to_string(),
+                None => status.to_string(),
+            });
+        }
+        serde_json::from_value(json).map_err(|e| e.to_string())
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        self.request(Method::GET, path, None).await
+    }
+
+    async fn put<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: impl Serialize,
+    ) -> Result<T, String> {
+        let body = serde_json::to_value(body).map_err(|e| e.to_string())?;
+        self.request(Method::PUT, path, Some(body)).await
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args
+        .iter()
+        .any(|a| a == "-h" || a == "--help" || a == "help")
+    {
+        println!("{}", USAGE);
+        return;
+    }
+    let runtime = tokio::runtime::Runtime::new().expect("Couldn't start the async runtime");
+    if let Err(e) = runtime.block_on(run(args)) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
+
+async fn run(args: Vec<String>) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let api = Api {
+        client: Client::new(),
+        config: load_config()?,
+    };
+    match args.as_slice() {
+        [] | ["today"] => today(&api).await,
+        ["projects"] => projects(&api).await,
+        ["track", projects @ ..] if !projects.is_empty() => {
+            let tracked: Vec<String> = api.put("/projects", projects).await?;
+            println!("Tracking {}", tracked.join(", "));
+            Ok(())
+        }
+        ["slots"] => slots(&api).await,
+        ["slot", day, time, goal @ ..] if goal.len() <= 1 => {
+            slot(&api, day, time, goal.first().copied()).await
+        }
+        ["test"] => {
+            let sent: Value = api.request(Method::POST, "/test", None).await?;
+            println!(
+                "Sent a test notification to {}",
+                sent["topic"].as_str().unwrap_or("ntfy")
+            );
+            Ok(())
+        }
+        ["summary"] => summary(&api, "/week").await,
+        ["summary", date] => summary(&api, &format!("/week?week={}", date)).await,
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+async fn today(api: &Api) -> Result<(), String> {
+    let p: Progress = api.get("/progress").await?;
+    if p.tracked.is_empty() {
+        println!("You aren't tracking a project this week, use `track <project>`.");
+        return Ok(());
+    }
+    println!("Tracking {}", p.tracked.join(", "));
+    println!("Today: {} of {}h", hm(p.today), p.today_goal);
+    match p.pace {
+        Some(pace) => println!("Week: {}\n{}", hm(p.week), pace.message()),
+        None => println!("Week: {}", hm(p.week)),
+    }
+    println!(
+        "{}",
+        if p.shipped {
+            "Shipped this week."
+        } else {
+            "Not shipped yet."
+        }
+    );
+    Ok(())
+}
+
+async fn projects(api: &Api) -> Result<(), String> {
+    let p: Projects = api.get("/projects").await?;
+    for project in &p.projects {
+        let mark = if p.tracked.contains(&project.name) {
+            "*"
+        } else {
+            " "
+        };
+        println!("{} {} ({})", mark, project.name, project.time);
+    }
+    // tracked projects without time this week
+    for name in p
+        .tracked
+        .iter()
+        .filter(|t| !p.projects.iter().any(|p| &p.name == *t))
+    {
+        println!("* {} (0 secs)", name);
+    }
+    Ok(())
+}
+
+/// Local minutes since monday 00:00 of a slot time.
+fn local(time: i64, offset: i32) -> i64 {
+    (time + offset as i64 / 60).rem_euclid(WEEK)
+}
+
+async fn slots(api: &Api) -> Result<(), String> {
+    let settings: Settings = api.get("/schedule").await?;
+    for (day, slot) in DAYS.iter().zip(settings.slots()) {
+        let minute = local(slot.time, settings.offset) % (24 * 60);
+        println!(
+            "{:<10} {:02}:{:02}  goal {}h  {:?}",
+            day,
+            minute / 60,
+            minute % 60,
+            slot.goal,
+            slot.window
+        );
+    }
+    Ok(())
+}
+
+async fn slot(api: &Api, day: &str, time: &str, goal: Option<&str>) -> Result<(), String> {
+    let day = DAYS
+        .iter()
+        .position(|d| d.starts_with(&day.to_lowercase()) && day.len() >= 2)
+        .ok_or(format!("Unknown day {}", day))?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| "Times look like 18:30")?;
+    let settings: Settings = api.get("/schedule").await?;
+    let goal = match goal {
+        Some(g) => g.parse().map_err(|_| "The goal is whole hours")?,
+        None => settings.slots()[day].goal,
+    };
+    // same as the web app, minutes since monday 00:00 UTC
+    let minutes = Duration::days(day as i64).num_minutes()
+        + (time.hour() * 60 + time.minute()) as i64
+        - settings.offset as i64 / 60;
+    let settings: Settings = api
+        .put("/schedule", settings.with_slot(day, minutes, goal))
+        .await?;
+    let minute = local(settings.slots()[day].time, settings.offset) % (24 * 60);
+    println!(
+        "{} reminder at {:02}:{:02}, goal {}h",
+        DAYS[day],
+        minute / 60,
+        minute % 60,
+        goal
+    );
+    Ok(())
+}
+
+async fn summary(api: &Api, path: &str) -> Result<(), String> {
+    let w: WeekData = api.get(path).await?;
+    println!("Week of {}", w.start.format("%d.%m.%Y"));
+    if !w.tracked.is_empty() {
+        println!("Tracked: {}", w.tracked.join(", "));
+    }
+    for d in &w.days {
+        let result = if d.seconds >= d.target {
+            "made it"
+        } else {
+            "missed"
+        };
+        println!(
+            "{:<10} {} of {}, {}",
+            DAYS.get(d.day as usize).unwrap_or(&"?"),
+            hm(d.seconds),
+            hm(d.target),
+            result
+        );
+    }
+    if !w.projects.is_empty() {
+        println!(
+            "Projects: {}",
+            w.projects
+                .iter()
+                .map(|p| format!("{} ({})", p.name, p.time))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    match w.submission {
+        Some(s) if s.url.is_empty() => println!("Shipped."),
+        Some(s) => println!("Shipped: {}", s.url),
+        None => println!("Not shipped."),
+    }
+    Ok(())
+}
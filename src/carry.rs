@@ -0,0 +1,99 @@
+#![cfg(feature = "ssr")]
+
+use crate::datatypes::CarryMode;
+
+/// Today's goal in seconds with the running balance spread over the days left in the week.
+/// `day` is the number of days since monday and `goals` the hour goal of every day, only days with
+/// a goal take a share and today counts as a remaining day.
+pub fn adjusted_goal(goal: i64, balance: i64, day: usize, goals: &[i32]) -> i64 {
+    if goal <= 0 {
+        return 0;
+    }
+    let remaining = goals.iter().skip(day).filter(|g| **g > 0).count().max(1) as i64;
+    (goal - balance / remaining).max(0)
+}
+
+/// The weekly goal in seconds minus whatever was carried over from previous weeks.
+pub fn adjusted_weekly_goal(goal: i64, bank: i64) -> i64 {
+    (goal - bank).max(0)
+}
+
+/// The new bank after a week ended `over` seconds above its (adjusted) goal.
+pub fn settle_week(mode: CarryMode, over: i64, cap: i32) -> i64 {
+    match mode {
+        CarryMode::Bank => over.clamp(-(cap as i64) * 60 * 60, cap as i64 * 60 * 60),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 60 * 60;
+
+    #[test]
+    fn spreads_over_days_with_a_goal() {
+        let every_day = [2, 2, 2, 2, 2, 2, 2];
+        let weekdays = [2, 2, 2, 2, 2, 0, 0];
+        // (goal, balance, day, goals, adjusted)
+        let cases = [
+            (2 * HOUR, 0, 0, every_day, 2 * HOUR),
+            // 3h behind on friday, three days left
+            (2 * HOUR, -3 * HOUR, 4, every_day, 3 * HOUR),
+            // the weekend has no goal so friday takes all of it
+            (2 * HOUR, -3 * HOUR, 4, weekdays, 5 * HOUR),
+            (2 * HOUR, -4 * HOUR, 3, weekdays, 4 * HOUR),
+            // ahead, down to 0 at most
+            (2 * HOUR, 3 * HOUR, 4, every_day, HOUR),
+            (2 * HOUR, 9 * HOUR, 4, weekdays, 0),
+            // days without a goal stay without one
+            (0, -3 * HOUR, 5, weekdays, 0),
+            // goals that were changed to 0 after today still leave today
+            (2 * HOUR, -2 * HOUR, 6, [0; 7], 4 * HOUR),
+        ];
+        for (goal, balance, day, goals, adjusted) in cases {
+            assert_eq!(
+                adjusted_goal(goal, balance, day, &goals),
+                adjusted,
+                "{} {} {} {:?}",
+                goal,
+                balance,
+                day,
+                goals
+            );
+        }
+    }
+
+    #[test]
+    fn weekly_goal() {
+        assert_eq!(adjusted_weekly_goal(14 * HOUR, 3 * HOUR), 11 * HOUR);
+        assert_eq!(adjusted_weekly_goal(14 * HOUR, -3 * HOUR), 17 * HOUR);
+        assert_eq!(adjusted_weekly_goal(14 * HOUR, 20 * HOUR), 0);
+    }
+
+    #[test]
+    fn bank_is_capped() {
+        // (over, cap in hours, bank)
+        let cases = [
+            (3 * HOUR, 5, 3 * HOUR),
+            (5 * HOUR, 5, 5 * HOUR),
+            (5 * HOUR + 1, 5, 5 * HOUR),
+            (40 * HOUR, 5, 5 * HOUR),
+            (-5 * HOUR, 5, -5 * HOUR),
+            (-40 * HOUR, 5, -5 * HOUR),
+            (3 * HOUR, 0, 0),
+        ];
+        for (over, cap, bank) in cases {
+            assert_eq!(
+                settle_week(CarryMode::Bank, over, cap),
+                bank,
+                "{} {}",
+                over,
+                cap
+            );
+        }
+        assert_eq!(settle_week(CarryMode::Week, 40 * HOUR, 5), 0);
+        assert_eq!(settle_week(CarryMode::Off, 40 * HOUR, 5), 0);
+    }
+}
@@ -1,14 +1,257 @@
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Data {
     pub username: String,
     pub projects: Vec<Project>,
-    pub primary: String,
+    /// Projects whose time counts toward the goals.
+    pub tracked: Vec<String>,
+    pub aliases: Vec<Alias>,
     pub settings: Settings,
+    pub carry: Carry,
+    pub project_carry: ProjectCarry,
+    /// Secret part of the users calendar feed URL.
+    pub feed_token: String,
+    pub pace: Option<Pace>,
+    pub long_goal: Option<LongGoal>,
+    pub milestones: Vec<Milestone>,
+    /// Minutes before the submission deadline to send alerts at.
+    pub deadline_alerts: Vec<i64>,
+    /// This weeks submission, if the user shipped.
+    pub submission: Option<Submission>,
+    /// Things to do before shipping the tracked projects.
+    pub checklist: Vec<ChecklistItem>,
+    pub channels: Channels,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Where notifications go.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Channels {
+    /// Topic on the ntfy server, empty for the username.
+    pub ntfy_topic: String,
+}
+
+/// How far the user is this week, for the API.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Progress {
+    pub tracked: Vec<String>,
+    /// Seconds on the tracked projects since the week started.
+    pub week: i64,
+    /// Seconds on the tracked projects since local midnight.
+    pub today: i64,
+    /// Todays goal in hours.
+    pub today_goal: i32,
+    pub pace: Option<Pace>,
+    pub shipped: bool,
+}
+
+/// Hackatime projects of this week next to the tracked ones, for the API.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Projects {
+    pub tracked: Vec<String>,
+    pub projects: Vec<Project>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ApiScope {
+    Read,
+    ReadWrite,
+}
+
+impl ApiScope {
+    pub fn from_i64(scope: i64) -> Self {
+        match scope {
+            1 => ApiScope::ReadWrite,
+            _ => ApiScope::Read,
+        }
+    }
+
+    pub fn to_i64(self) -> i64 {
+        match self {
+            ApiScope::Read => 0,
+            ApiScope::ReadWrite => 1,
+        }
+    }
+}
+
+/// A personal API token, the secret is only shown when it's created.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub scope: ApiScope,
+    pub created: NaiveDateTime,
+    pub last_used: Option<NaiveDateTime>,
+}
+
+/// What the user did on a project that day, for devlogs.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct JournalEntry {
+    pub project: String,
+    pub day: NaiveDate,
+    pub text: String,
+}
+
+/// The journal page, `entries` has one per tracked project with a draft if nothing was written.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+    /// Local minute of the evening prompt.
+    pub prompt: Option<i32>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChecklistItem {
+    pub id: i64,
+    pub project: String,
+    pub name: String,
+    pub done: bool,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Role {
+    User,
+    /// Can see the admin page and disable accounts.
+    Admin,
+}
+
+impl Role {
+    pub fn from_i64(role: i64) -> Self {
+        match role {
+            1 => Role::Admin,
+            _ => Role::User,
+        }
+    }
+
+    pub fn to_i64(self) -> i64 {
+        match self {
+            Role::User => 0,
+            Role::Admin => 1,
+        }
+    }
+}
+
+/// Overview for the admin page.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AdminData {
+    pub users: i64,
+    pub disabled: i64,
+    /// Users that got a reminder in the last 7 days.
+    pub active: i64,
+    /// Outcomes per UTC day of the last two weeks, oldest first.
+    pub days: Vec<EventDay>,
+    /// The last minute the scheduler went through and when it was done with it.
+    pub scheduler: Option<(NaiveDateTime, NaiveDateTime)>,
+}
+
+/// Successful and failed reminders, ntfy and Hackatime requests of a day.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct EventDay {
+    pub day: NaiveDate,
+    pub reminders: (i64, i64),
+    pub ntfy: (i64, i64),
+    pub hackatime: (i64, i64),
+}
+
+/// A logged request or reminder.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Event {
+    pub time: NaiveDateTime,
+    pub kind: String,
+    pub ok: bool,
+    /// The notification title, or the error.
+    pub detail: String,
+}
+
+/// A stored reminder result.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Evaluation {
+    pub time: NaiveDateTime,
+    /// 0 is monday.
+    pub day: i64,
+    pub window_start: NaiveDateTime,
+    pub target: i64,
+    pub seconds: i64,
+}
+
+/// Everything the admin page shows about one user.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct AdminUser {
+    pub username: String,
+    pub email: String,
+    pub role: Role,
+    pub disabled: bool,
+    pub settings: Settings,
+    pub tracked: Vec<String>,
+    /// Newest first.
+    pub notifications: Vec<Event>,
+    pub evaluation: Option<Evaluation>,
+}
+
+/// A browser the user is logged in with.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct SessionInfo {
+    pub id: i64,
+    pub created: NaiveDateTime,
+    pub last_used: NaiveDateTime,
+    /// Whether it's the browser asking.
+    pub current: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Submission {
+    pub time: NaiveDateTime,
+    /// Empty if the user didn't paste one.
+    pub url: String,
+}
+
+/// A dated step of a tracked project.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Milestone {
+    pub id: i64,
+    pub project: String,
+    pub name: String,
+    /// Hours on the project by the due date.
+    pub target: Option<i32>,
+    pub due: NaiveDate,
+    /// Days before `due` to send a reminder on.
+    pub offsets: Vec<i32>,
+    pub done: bool,
+    /// Seconds on the project so far.
+    pub progress: i64,
+}
+
+/// A goal for the whole event on top of the weekly goals, `start` and `end` are inclusive.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct LongGoal {
+    pub hours: i32,
+    pub project: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GoalStatus {
+    pub goal: LongGoal,
+    /// Seconds so far.
+    pub done: i64,
+    /// Seconds per week needed to make it.
+    pub required: i64,
+    /// Seconds behind working on it evenly.
+    pub behind: i64,
+    /// Seconds left at the end of every day so far.
+    pub remaining: Vec<(NaiveDate, i64)>,
+}
+
+/// Projected total of the week next to the weekly goal, both in seconds.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Pace {
+    pub projected: i64,
+    pub goal: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Project {
     pub name: String,
     pub time: String,
@@ -16,11 +259,229 @@ pub struct Project {
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Settings {
-    pub monday: (i64, i32),
-    pub tuesday: (i64, i32),
-    pub wednesday: (i64, i32),
-    pub thursday: (i64, i32),
-    pub friday: (i64, i32),
-    pub saturday: (i64, i32),
-    pub sunday: (i64, i32),
+    pub monday: Slot,
+    pub tuesday: Slot,
+    pub wednesday: Slot,
+    pub thursday: Slot,
+    pub friday: Slot,
+    pub saturday: Slot,
+    pub sunday: Slot,
+    /// The users offset from UTC in seconds, used for windows that depend on local time.
+    pub offset: i32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Slot {
+    /// Minutes since monday 00:00 UTC.
+    pub time: i64,
+    /// Goal in hours.
+    pub goal: i32,
+    pub window: Window,
+}
+
+/// The time span a reminder measures.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Window {
+    /// Since the reminder of the previous day.
+    PreviousSlot,
+    /// Since midnight in the users timezone.
+    LocalMidnight,
+    /// The last 24 hours.
+    Rolling,
+    /// Since the start of the week, the goal is the sum of all goals up to this day.
+    WeekToDate,
+}
+
+impl Window {
+    pub fn from_i64(window: i64) -> Self {
+        match window {
+            1 => Window::LocalMidnight,
+            2 => Window::Rolling,
+            3 => Window::WeekToDate,
+            _ => Window::PreviousSlot,
+        }
+    }
+
+    pub fn to_i64(self) -> i64 {
+        match self {
+            Window::PreviousSlot => 0,
+            Window::LocalMidnight => 1,
+            Window::Rolling => 2,
+            Window::WeekToDate => 3,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CalendarData {
+    pub vacations: Vec<Vacation>,
+    pub overrides: Vec<Override>,
+    pub calendars: Vec<CalendarFile>,
+}
+
+/// How a week went, weeks start at the monday 04:00 UTC deadline.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct WeekData {
+    pub start: NaiveDate,
+    pub projects: Vec<Project>,
+    pub tracked: Vec<String>,
+    /// Results of the reminders sent that week.
+    pub days: Vec<DayResult>,
+    pub submission: Option<Submission>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DayResult {
+    /// 0 is monday.
+    pub day: i64,
+    pub target: i64,
+    pub seconds: i64,
+}
+
+/// Charts of the stats page, built from the stored snapshots.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Stats {
+    /// Tracked time of each day of the current week.
+    pub days: Vec<Bar>,
+    /// Time on all projects of each week of the event.
+    pub weeks: Vec<Bar>,
+    /// This weeks time per project.
+    pub projects: Vec<Bar>,
+    pub goal: Option<GoalStatus>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Bar {
+    pub label: String,
+    pub seconds: i64,
+    pub goal: Option<i64>,
+}
+
+/// An imported iCalendar file, holidays skip reminders and busy events delay them.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct CalendarFile {
+    pub id: i64,
+    pub name: String,
+    /// Empty for uploaded files, URLs are fetched again every hour.
+    pub url: String,
+    pub events: i32,
+}
+
+/// No reminders are sent from `start` to `end`, both inclusive.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Vacation {
+    pub id: i64,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+/// Replaces the regular reminder on a single date.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Override {
+    pub date: NaiveDate,
+    /// Minutes after local midnight.
+    pub time: i32,
+    /// Goal in hours.
+    pub goal: i32,
+}
+
+/// Maps Hackatime projects with a different name onto a tracked project.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Alias {
+    pub id: i64,
+    pub project: String,
+    pub kind: AliasKind,
+    pub pattern: String,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum AliasKind {
+    Exact,
+    /// `*` matches any text, `?` a single character.
+    Glob,
+    Regex,
+}
+
+impl AliasKind {
+    pub fn from_i64(kind: i64) -> Self {
+        match kind {
+            1 => AliasKind::Glob,
+            2 => AliasKind::Regex,
+            _ => AliasKind::Exact,
+        }
+    }
+
+    pub fn to_i64(self) -> i64 {
+        match self {
+            AliasKind::Exact => 0,
+            AliasKind::Glob => 1,
+            AliasKind::Regex => 2,
+        }
+    }
+}
+
+/// What happens to the tracked projects when a new week starts.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ProjectCarry {
+    Clear,
+    Keep,
+    /// Track the project with the most time in the past n days.
+    MostWorked(i32),
+}
+
+impl ProjectCarry {
+    pub fn from_i64(mode: i64, days: i32) -> Self {
+        match mode {
+            1 => ProjectCarry::Keep,
+            2 => ProjectCarry::MostWorked(days),
+            _ => ProjectCarry::Clear,
+        }
+    }
+
+    pub fn to_i64(self) -> i64 {
+        match self {
+            ProjectCarry::Clear => 0,
+            ProjectCarry::Keep => 1,
+            ProjectCarry::MostWorked(_) => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+pub enum CarryMode {
+    /// Missed or extra time is forgotten after each reminder.
+    Off,
+    /// The difference to a day's goal is spread over the remaining days of the week.
+    Week,
+    /// Like `Week`, but the weekly result is kept in a capped time bank for the next week.
+    Bank,
+}
+
+impl CarryMode {
+    pub fn from_i64(mode: i64) -> Self {
+        match mode {
+            1 => CarryMode::Week,
+            2 => CarryMode::Bank,
+            _ => CarryMode::Off,
+        }
+    }
+
+    pub fn to_i64(self) -> i64 {
+        match self {
+            CarryMode::Off => 0,
+            CarryMode::Week => 1,
+            CarryMode::Bank => 2,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Carry {
+    pub mode: CarryMode,
+    /// Maximum time bank in hours, in both directions.
+    pub cap: i32,
+    /// Seconds ahead (positive) or behind (negative) of this week's goals.
+    pub balance: i64,
+    /// Seconds carried over from previous weeks.
+    pub bank: i64,
 }
@@ -1,8 +1,35 @@
 #![cfg(feature = "ssr")]
 
-use sqlx::{query, SqlitePool};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::{query, sqlite::SqliteRow, Row, SqlitePool};
 use std::sync::LazyLock;
 
+use crate::{
+    datatypes::{
+        Alias, AliasKind, CalendarFile, Carry, CarryMode, ChecklistItem, JournalEntry, LongGoal,
+        Milestone, Override, ProjectCarry, Role, Settings, Slot, Submission, Vacation, Window,
+    },
+    ical::{self, Occurrence},
+    projects::{daily_totals, is_pattern},
+};
+
+/// Days ahead that calendar occurrences are stored for, they're expanded again every hour.
+const CALENDAR_DAYS: i64 = 14;
+
+/// Every project starts with these checklist items.
+const DEFAULT_CHECKLIST: [&str; 4] = ["README", "demo link", "repo link", "devlog"];
+
+const DAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
 pub static POOL: LazyLock<SqlitePool> =
     LazyLock::new(|| SqlitePool::connect_lazy("sqlite://db.sqlite").unwrap());
 
@@ -26,4 +53,861 @@ pub async fn prep_db() {
     	"sunday_goal"	INTEGER,
         PRIMARY KEY("username")
     );"#).execute(&*POOL).await.unwrap();
-}
\ No newline at end of file
+
+    add_column("users", "carry_mode", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "bank_cap", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "balance", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "bank", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "utc_offset", "INTEGER NOT NULL DEFAULT 0").await;
+
+    query(r#"CREATE TABLE IF NOT EXISTS "vacations" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "start"	TEXT NOT NULL,
+        "end"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+    query(r#"CREATE TABLE IF NOT EXISTS "overrides" (
+        "username"	TEXT NOT NULL,
+        "date"	TEXT NOT NULL,
+        "time"	INTEGER NOT NULL,
+        "goal"	INTEGER NOT NULL,
+        PRIMARY KEY("username", "date")
+    );"#).execute(&*POOL).await.unwrap();
+    add_column("users", "project_carry", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "project_carry_days", "INTEGER NOT NULL DEFAULT 7").await;
+
+    query(r#"CREATE TABLE IF NOT EXISTS "projects" (
+        "username"	TEXT NOT NULL,
+        "name"	TEXT NOT NULL,
+        PRIMARY KEY("username", "name")
+    );"#).execute(&*POOL).await.unwrap();
+    // projects used to be a single column on users
+    query(r#"INSERT OR IGNORE INTO projects (username, name) SELECT username, project FROM users WHERE project != ''"#)
+        .execute(&*POOL)
+        .await
+        .unwrap();
+    query("UPDATE users SET project = ''").execute(&*POOL).await.unwrap();
+
+    query(r#"CREATE TABLE IF NOT EXISTS "aliases" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        "kind"	INTEGER NOT NULL,
+        "pattern"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+    for day in DAYS {
+        // sunday used to always measure the whole week
+        let default = if day == "sunday" { Window::WeekToDate } else { Window::PreviousSlot };
+        add_column(
+            "users",
+            &format!("{}_window", day),
+            &format!("INTEGER NOT NULL DEFAULT {}", default.to_i64()),
+        )
+        .await;
+    }
+    add_column("users", "feed_token", "TEXT NOT NULL DEFAULT ''").await;
+    // no long-term goal while goal_hours is 0
+    add_column("users", "goal_hours", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "goal_project", "TEXT NOT NULL DEFAULT ''").await;
+    add_column("users", "goal_start", "TEXT NOT NULL DEFAULT ''").await;
+    add_column("users", "goal_end", "TEXT NOT NULL DEFAULT ''").await;
+
+    query(r#"CREATE TABLE IF NOT EXISTS "calendars" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "name"	TEXT NOT NULL,
+        "url"	TEXT NOT NULL,
+        "content"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+    // holidays and busy times of the calendars in UTC, so reminders don't parse calendars
+    query(r#"CREATE TABLE IF NOT EXISTS "calendar_occurrences" (
+        "calendar"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "start"	TEXT NOT NULL,
+        "end"	TEXT NOT NULL,
+        "holiday"	INTEGER NOT NULL,
+        "busy"	INTEGER NOT NULL
+    );"#).execute(&*POOL).await.unwrap();
+    query(r#"CREATE INDEX IF NOT EXISTS "calendar_occurrences_user" ON "calendar_occurrences" ("username", "start")"#)
+        .execute(&*POOL)
+        .await
+        .unwrap();
+
+    // per project time of every UTC day
+    query(r#"CREATE TABLE IF NOT EXISTS "snapshots" (
+        "username"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        "day"	TEXT NOT NULL,
+        "seconds"	INTEGER NOT NULL,
+        PRIMARY KEY("username", "project", "day")
+    );"#).execute(&*POOL).await.unwrap();
+    add_column("users", "backfilled", "INTEGER NOT NULL DEFAULT 0").await;
+    // failed backfills, the backfill is given up after a few
+    add_column("users", "backfill_failures", "INTEGER NOT NULL DEFAULT 0").await;
+    // every reminder that was sent and how the user did
+    query(r#"CREATE TABLE IF NOT EXISTS "evaluations" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "time"	TEXT NOT NULL,
+        "day"	INTEGER NOT NULL,
+        "window_start"	TEXT NOT NULL,
+        "target"	INTEGER NOT NULL,
+        "seconds"	INTEGER NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+
+    add_column("users", "deadline_alerts", "TEXT NOT NULL DEFAULT '1440,360,60,15'").await;
+    // one row per week the user shipped, keyed by the weeks monday
+    query(r#"CREATE TABLE IF NOT EXISTS "submissions" (
+        "username"	TEXT NOT NULL,
+        "week"	TEXT NOT NULL,
+        "time"	TEXT NOT NULL,
+        PRIMARY KEY("username", "week")
+    );"#).execute(&*POOL).await.unwrap();
+    add_column("submissions", "url", "TEXT NOT NULL DEFAULT ''").await;
+    // secret for the buttons in notifications that act on the user
+    add_column("users", "action_token", "TEXT NOT NULL DEFAULT ''").await;
+
+    query(r#"CREATE TABLE IF NOT EXISTS "milestones" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        "name"	TEXT NOT NULL,
+        "target"	INTEGER,
+        "due"	TEXT NOT NULL,
+        "offsets"	TEXT NOT NULL,
+        "done"	INTEGER NOT NULL DEFAULT 0,
+        "token"	TEXT NOT NULL,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+
+    query(r#"CREATE TABLE IF NOT EXISTS "checklist" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        "name"	TEXT NOT NULL,
+        "done"	INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+    // projects that got the default checklist, so removed items stay removed
+    query(r#"CREATE TABLE IF NOT EXISTS "checklist_projects" (
+        "username"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        PRIMARY KEY("username", "project")
+    );"#).execute(&*POOL).await.unwrap();
+
+    query(r#"CREATE TABLE IF NOT EXISTS "journal" (
+        "username"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        "day"	TEXT NOT NULL,
+        "text"	TEXT NOT NULL,
+        PRIMARY KEY("username", "project", "day")
+    );"#).execute(&*POOL).await.unwrap();
+    // local minute of the evening journal prompt, no prompt if NULL
+    add_column("users", "journal_prompt", "INTEGER").await;
+
+    // one pending login code per user, `sent` is when it was sent
+    query(r#"CREATE TABLE IF NOT EXISTS "codes" (
+        "username"	TEXT NOT NULL,
+        "code"	TEXT NOT NULL,
+        "sent"	TEXT NOT NULL,
+        "attempts"	INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY("username")
+    );"#).execute(&*POOL).await.unwrap();
+    // when the username asked for the first code of the rate limit window, and how many since
+    add_column("codes", "window_start", "TEXT NOT NULL DEFAULT ''").await;
+    add_column("codes", "requests", "INTEGER NOT NULL DEFAULT 0").await;
+    // the secret topic a new account picked, the code went there, empty for existing accounts
+    add_column("codes", "topic", "TEXT NOT NULL DEFAULT ''").await;
+    query(r#"CREATE TABLE IF NOT EXISTS "sessions" (
+        "token"	TEXT NOT NULL,
+        "username"	TEXT NOT NULL,
+        "created"	TEXT NOT NULL,
+        "last_used"	TEXT NOT NULL,
+        PRIMARY KEY("token")
+    );"#).execute(&*POOL).await.unwrap();
+
+    // tracked projects of past weeks
+    query(r#"CREATE TABLE IF NOT EXISTS "week_projects" (
+        "username"	TEXT NOT NULL,
+        "week"	TEXT NOT NULL,
+        "project"	TEXT NOT NULL,
+        PRIMARY KEY("username", "week", "project")
+    );"#).execute(&*POOL).await.unwrap();
+
+    // accounts of the OpenID Connect provider, `subject` is its id for the user
+    query(r#"CREATE TABLE IF NOT EXISTS "identities" (
+        "issuer"	TEXT NOT NULL,
+        "subject"	TEXT NOT NULL,
+        "username"	TEXT NOT NULL,
+        "email"	TEXT NOT NULL DEFAULT '',
+        PRIMARY KEY("issuer", "subject")
+    );"#).execute(&*POOL).await.unwrap();
+    // logins that were sent to the provider and haven't come back yet
+    query(r#"CREATE TABLE IF NOT EXISTS "oidc_states" (
+        "state"	TEXT NOT NULL,
+        "nonce"	TEXT NOT NULL,
+        "created"	TEXT NOT NULL,
+        PRIMARY KEY("state")
+    );"#).execute(&*POOL).await.unwrap();
+    add_column("users", "email", "TEXT NOT NULL DEFAULT ''").await;
+    // logins that wait for the logged in user to confirm linking them to their account
+    query(r#"CREATE TABLE IF NOT EXISTS "oidc_links" (
+        "token"	TEXT NOT NULL,
+        "username"	TEXT NOT NULL,
+        "issuer"	TEXT NOT NULL,
+        "subject"	TEXT NOT NULL,
+        "email"	TEXT NOT NULL DEFAULT '',
+        "created"	TEXT NOT NULL,
+        PRIMARY KEY("token")
+    );"#).execute(&*POOL).await.unwrap();
+
+    // personal tokens for the JSON API, only the sha256 of the secret is stored
+    query(r#"CREATE TABLE IF NOT EXISTS "api_tokens" (
+        "id"	INTEGER NOT NULL,
+        "username"	TEXT NOT NULL,
+        "name"	TEXT NOT NULL,
+        "hash"	TEXT NOT NULL UNIQUE,
+        "scope"	INTEGER NOT NULL DEFAULT 0,
+        "created"	TEXT NOT NULL,
+        "last_used"	TEXT,
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+    // notifications go to the username if empty
+    add_column("users", "ntfy_topic", "TEXT NOT NULL DEFAULT ''").await;
+    // disabled users get no notifications and can't log in
+    add_column("users", "disabled", "INTEGER NOT NULL DEFAULT 0").await;
+    add_column("users", "role", "INTEGER NOT NULL DEFAULT 0").await;
+
+    // outcomes of reminders, ntfy and Hackatime requests for the admin page, `kind` is one of
+    // "reminder", "ntfy" and "hackatime"
+    query(r#"CREATE TABLE IF NOT EXISTS "events" (
+        "id"	INTEGER NOT NULL,
+        "time"	TEXT NOT NULL,
+        "kind"	TEXT NOT NULL,
+        "username"	TEXT NOT NULL,
+        "ok"	INTEGER NOT NULL,
+        "detail"	TEXT NOT NULL DEFAULT '',
+        PRIMARY KEY("id" AUTOINCREMENT)
+    );"#).execute(&*POOL).await.unwrap();
+}
+
+pub fn settings_from_row(row: &SqliteRow) -> Result<Settings, sqlx::Error> {
+    let slot = |day: &str| -> Result<Slot, sqlx::Error> {
+        Ok(Slot {
+            time: row.try_get(day)?,
+            goal: row.try_get(format!("{}_goal", day).as_str())?,
+            window: Window::from_i64(row.try_get(format!("{}_window", day).as_str())?),
+        })
+    };
+    Ok(Settings {
+        monday: slot("monday")?,
+        tuesday: slot("tuesday")?,
+        wednesday: slot("wednesday")?,
+        thursday: slot("thursday")?,
+        friday: slot("friday")?,
+        saturday: slot("saturday")?,
+        sunday: slot("sunday")?,
+        offset: row.try_get("utc_offset")?,
+    })
+}
+
+pub async fn tracked_projects(username: &str) -> Result<Vec<String>, sqlx::Error> {
+    query("SELECT name FROM projects WHERE username = ? ORDER BY name")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| r.try_get("name"))
+        .collect()
+}
+
+/// Remembers the projects tracked in `week`, the monday of a week going from submission to
+/// submission like `week_of`.
+pub async fn save_week_projects(
+    username: &str,
+    week: NaiveDate,
+    projects: &[String],
+) -> Result<(), sqlx::Error> {
+    let week = week.to_string();
+    let mut tx = POOL.begin().await?;
+    query("DELETE FROM week_projects WHERE username = ? AND week = ?")
+        .bind(username)
+        .bind(week.clone())
+        .execute(&mut *tx)
+        .await?;
+    for project in projects {
+        query("INSERT OR IGNORE INTO week_projects (username, week, project) VALUES (?, ?, ?)")
+            .bind(username)
+            .bind(week.clone())
+            .bind(project)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+pub async fn week_projects(username: &str, week: NaiveDate) -> Result<Vec<String>, sqlx::Error> {
+    query("SELECT project FROM week_projects WHERE username = ? AND week = ? ORDER BY project")
+        .bind(username)
+        .bind(week.to_string())
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| r.try_get("project"))
+        .collect()
+}
+
+pub async fn aliases(username: &str) -> Result<Vec<Alias>, sqlx::Error> {
+    query("SELECT * FROM aliases WHERE username = ? ORDER BY id")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            Ok(Alias {
+                id: r.try_get("id")?,
+                project: r.try_get("project")?,
+                kind: AliasKind::from_i64(r.try_get("kind")?),
+                pattern: r.try_get("pattern")?,
+            })
+        })
+        .collect()
+}
+
+pub async fn vacations(username: &str) -> Result<Vec<Vacation>, sqlx::Error> {
+    query("SELECT * FROM vacations WHERE username = ? ORDER BY start")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            Ok(Vacation {
+                id: r.try_get("id")?,
+                start: parse_date(r.try_get("start")?)?,
+                end: parse_date(r.try_get("end")?)?,
+            })
+        })
+        .collect()
+}
+
+pub async fn overrides(username: &str) -> Result<Vec<Override>, sqlx::Error> {
+    query("SELECT * FROM overrides WHERE username = ? ORDER BY date")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(override_from_row)
+        .collect()
+}
+
+/// Overrides from `from` to `to`, both included.
+pub async fn overrides_between(
+    username: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<Override>, sqlx::Error> {
+    query("SELECT * FROM overrides WHERE username = ? AND date >= ? AND date <= ? ORDER BY date")
+        .bind(username)
+        .bind(from.to_string())
+        .bind(to.to_string())
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(override_from_row)
+        .collect()
+}
+
+pub async fn on_vacation(username: &str, date: NaiveDate) -> Result<bool, sqlx::Error> {
+    // dates are stored as YYYY-MM-DD so they compare correctly as text
+    Ok(query("SELECT 1 FROM vacations WHERE username = ?1 AND start <= ?2 AND end >= ?2")
+        .bind(username)
+        .bind(date.to_string())
+        .fetch_optional(&*POOL)
+        .await?
+        .is_some())
+}
+
+pub async fn day_override(username: &str, date: NaiveDate) -> Result<Option<Override>, sqlx::Error> {
+    query("SELECT * FROM overrides WHERE username = ? AND date = ?")
+        .bind(username)
+        .bind(date.to_string())
+        .fetch_optional(&*POOL)
+        .await?
+        .as_ref()
+        .map(override_from_row)
+        .transpose()
+}
+
+pub async fn calendars(username: &str) -> Result<Vec<CalendarFile>, sqlx::Error> {
+    query("SELECT * FROM calendars WHERE username = ? ORDER BY id")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            let content: String = r.try_get("content")?;
+            Ok(CalendarFile {
+                id: r.try_get("id")?,
+                name: r.try_get("name")?,
+                url: r.try_get("url")?,
+                events: ical::parse(&content, 0).len() as i32,
+            })
+        })
+        .collect()
+}
+
+/// Every stored (project, day, seconds) of a user.
+pub async fn snapshots(username: &str) -> Result<Vec<(String, NaiveDate, i64)>, sqlx::Error> {
+    query("SELECT * FROM snapshots WHERE username = ? ORDER BY day")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            Ok((
+                r.try_get("project")?,
+                parse_date(r.try_get("day")?)?,
+                r.try_get("seconds")?,
+            ))
+        })
+        .collect()
+}
+
+/// Stores the holidays and busy times of a calendar from yesterday until `CALENDAR_DAYS` ahead,
+/// `offset` is used for times without a timezone.
+pub async fn save_occurrences(
+    id: i64,
+    username: &str,
+    content: &str,
+    offset: i32,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let occurrences = ical::occurrences(
+        &ical::parse(content, offset),
+        offset,
+        now - Duration::days(1),
+        now + Duration::days(CALENDAR_DAYS),
+    );
+    let mut tx = POOL.begin().await?;
+    query("DELETE FROM calendar_occurrences WHERE calendar = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for o in occurrences.iter().filter(|o| o.holiday || o.busy) {
+        query("INSERT INTO calendar_occurrences (calendar, username, start, end, holiday, busy) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(id)
+            .bind(username)
+            .bind(o.start.to_string())
+            .bind(o.end.to_string())
+            .bind(o.holiday)
+            .bind(o.busy)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+/// Expands all of a users calendars again, for when their offset changed.
+pub async fn expand_calendars(username: &str, offset: i32) -> Result<(), sqlx::Error> {
+    let rows = query("SELECT id, content FROM calendars WHERE username = ?")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?;
+    for r in rows {
+        save_occurrences(r.get("id"), username, r.get("content"), offset).await?;
+    }
+    Ok(())
+}
+
+/// Holidays and busy times of a users calendars overlapping `from` to `to` (UTC).
+pub async fn calendar_occurrences(
+    username: &str,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<Occurrence>, sqlx::Error> {
+    query("SELECT * FROM calendar_occurrences WHERE username = ? AND start < ? AND end > ?")
+        .bind(username)
+        .bind(to.to_string())
+        .bind(from.to_string())
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            Ok(Occurrence {
+                start: parse_time(r.try_get("start")?)?,
+                end: parse_time(r.try_get("end")?)?,
+                holiday: r.try_get("holiday")?,
+                busy: r.try_get("busy")?,
+            })
+        })
+        .collect()
+}
+
+fn override_from_row(row: &SqliteRow) -> Result<Override, sqlx::Error> {
+    Ok(Override {
+        date: parse_date(row.try_get("date")?)?,
+        time: row.try_get("time")?,
+        goal: row.try_get("goal")?,
+    })
+}
+
+fn parse_date(date: String) -> Result<NaiveDate, sqlx::Error> {
+    date.parse().map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+fn parse_time(time: String) -> Result<NaiveDateTime, sqlx::Error> {
+    NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S%.f")
+        .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+}
+
+pub fn project_carry_from_row(row: &SqliteRow) -> Result<ProjectCarry, sqlx::Error> {
+    Ok(ProjectCarry::from_i64(
+        row.try_get("project_carry")?,
+        row.try_get("project_carry_days")?,
+    ))
+}
+
+/// A users milestones, `progress` is filled in from the snapshots.
+pub async fn milestones(username: &str) -> Result<Vec<Milestone>, sqlx::Error> {
+    let snapshots = snapshots(username).await?;
+    let aliases = aliases(username).await?;
+    query("SELECT * FROM milestones WHERE username = ? ORDER BY due")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            let project: String = r.try_get("project")?;
+            let offsets: String = r.try_get("offsets")?;
+            Ok(Milestone {
+                id: r.try_get("id")?,
+                progress: daily_totals(&snapshots, &project, &aliases)
+                    .iter()
+                    .map(|d| d.1)
+                    .sum(),
+                project,
+                name: r.try_get("name")?,
+                target: r.try_get("target")?,
+                due: parse_date(r.try_get("due")?)?,
+                offsets: offsets.split(',').filter_map(|o| o.trim().parse().ok()).collect(),
+                done: r.try_get("done")?,
+            })
+        })
+        .collect()
+}
+
+/// Gives projects seen for the first time the default checklist items, patterns get none.
+pub async fn create_checklists(username: &str, projects: &[String]) -> Result<(), sqlx::Error> {
+    for project in projects.iter().filter(|p| !is_pattern(p)) {
+        let new = query("INSERT OR IGNORE INTO checklist_projects (username, project) VALUES (?, ?)")
+            .bind(username)
+            .bind(project)
+            .execute(&*POOL)
+            .await?
+            .rows_affected()
+            > 0;
+        if new {
+            for name in DEFAULT_CHECKLIST {
+                query("INSERT INTO checklist (username, project, name) VALUES (?, ?, ?)")
+                    .bind(username)
+                    .bind(project)
+                    .bind(name)
+                    .execute(&*POOL)
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The checklists of `projects`.
+pub async fn checklist(username: &str, projects: &[String]) -> Result<Vec<ChecklistItem>, sqlx::Error> {
+    let mut items = Vec::new();
+    for project in projects {
+        for r in query("SELECT * FROM checklist WHERE username = ? AND project = ? ORDER BY id")
+            .bind(username)
+            .bind(project)
+            .fetch_all(&*POOL)
+            .await?
+        {
+            items.push(ChecklistItem {
+                id: r.try_get("id")?,
+                project: r.try_get("project")?,
+                name: r.try_get("name")?,
+                done: r.try_get("done")?,
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Written journal entries from `start` to `end`, both inclusive.
+pub async fn journal(
+    username: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<JournalEntry>, sqlx::Error> {
+    query("SELECT * FROM journal WHERE username = ? AND day >= ? AND day <= ? ORDER BY day, project")
+        .bind(username)
+        .bind(start.to_string())
+        .bind(end.to_string())
+        .fetch_all(&*POOL)
+        .await?
+        .iter()
+        .map(|r| {
+            Ok(JournalEntry {
+                project: r.try_get("project")?,
+                day: parse_date(r.try_get("day")?)?,
+                text: r.try_get("text")?,
+            })
+        })
+        .collect()
+}
+
+pub fn deadline_alerts_from_row(row: &SqliteRow) -> Result<Vec<i64>, sqlx::Error> {
+    let alerts: String = row.try_get("deadline_alerts")?;
+    Ok(alerts.split(',').filter_map(|a| a.trim().parse().ok()).collect())
+}
+
+pub async fn shipped(username: &str, week: NaiveDate) -> Result<bool, sqlx::Error> {
+    Ok(submission(username, week).await?.is_some())
+}
+
+pub async fn submission(username: &str, week: NaiveDate) -> Result<Option<Submission>, sqlx::Error> {
+    let row = match query("SELECT time, url FROM submissions WHERE username = ? AND week = ?")
+        .bind(username)
+        .bind(week.to_string())
+        .fetch_optional(&*POOL)
+        .await?
+    {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let time: String = row.try_get("time")?;
+    Ok(Some(Submission {
+        time: NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S%.f")
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?,
+        url: row.try_get("url")?,
+    }))
+}
+
+/// Marks the week as shipped, a url replaces the one given before.
+pub async fn save_submission(username: &str, week: NaiveDate, url: &str) -> Result<(), sqlx::Error> {
+    query("INSERT INTO submissions (username, week, time, url) VALUES (?, ?, ?, ?) ON CONFLICT (username, week) DO UPDATE SET url = CASE WHEN excluded.url = '' THEN url ELSE excluded.url END")
+        .bind(username)
+        .bind(week.to_string())
+        .bind(Utc::now().naive_utc().to_string())
+        .bind(url)
+        .execute(&*POOL)
+        .await?;
+    Ok(())
+}
+
+/// The users action token, created the first time it's needed.
+pub async fn action_token(username: &str) -> Result<String, sqlx::Error> {
+    let token: String = query("SELECT action_token FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_one(&*POOL)
+        .await?
+        .try_get("action_token")?;
+    if !token.is_empty() {
+        return Ok(token);
+    }
+    let token = new_token();
+    query("UPDATE users SET action_token = ? WHERE username = ?")
+        .bind(token.clone())
+        .bind(username)
+        .execute(&*POOL)
+        .await?;
+    Ok(token)
+}
+
+pub fn long_goal_from_row(row: &SqliteRow) -> Result<Option<LongGoal>, sqlx::Error> {
+    let hours: i32 = row.try_get("goal_hours")?;
+    if hours <= 0 {
+        return Ok(None);
+    }
+    Ok(Some(LongGoal {
+        hours,
+        project: row.try_get("goal_project")?,
+        start: parse_date(row.try_get("goal_start")?)?,
+        end: parse_date(row.try_get("goal_end")?)?,
+    }))
+}
+
+pub fn carry_from_row(row: &SqliteRow) -> Result<Carry, sqlx::Error> {
+    Ok(Carry {
+        mode: CarryMode::from_i64(row.try_get("carry_mode")?),
+        cap: row.try_get("bank_cap")?,
+        balance: row.try_get("balance")?,
+        bank: row.try_get("bank")?,
+    })
+}
+
+/// Adds a user with the default schedule unless they exist, returns the feed token a new user
+/// gets.
+pub async fn create_user(username: &str) -> Result<String, sqlx::Error> {
+    let feed_token = new_token();
+    query("INSERT OR IGNORE INTO users (username, project, monday, monday_goal, tuesday, tuesday_goal, wednesday, wednesday_goal, thursday, thursday_goal, friday, friday_goal, saturday, saturday_goal, sunday, sunday_goal, feed_token) VALUES (?, '', 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 3, 0, 3, ?)")
+        .bind(username)
+        .bind(feed_token.clone())
+        .execute(&*POOL)
+        .await?;
+    Ok(feed_token)
+}
+
+/// The ntfy topic of a users notifications.
+pub async fn ntfy_topic(username: &str) -> Result<String, sqlx::Error> {
+    let topic = query("SELECT ntfy_topic FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&*POOL)
+        .await?
+        .map(|r| r.get::<String, _>("ntfy_topic"))
+        .unwrap_or_default();
+    if topic.is_empty() {
+        return Ok(username.to_string());
+    }
+    Ok(topic)
+}
+
+/// Whether login codes to `username` only reach its owner, the default topic is public.
+pub async fn has_secret_topic(username: &str) -> Result<bool, sqlx::Error> {
+    let topic = ntfy_topic(username).await?;
+    Ok(!topic.eq_ignore_ascii_case(username))
+}
+
+/// Sets where notifications and login codes go, false if there's no such user.
+pub async fn set_ntfy_topic(username: &str, topic: &str) -> Result<bool, sqlx::Error> {
+    Ok(query("UPDATE users SET ntfy_topic = ? WHERE username = ?")
+        .bind(topic)
+        .bind(username)
+        .execute(&*POOL)
+        .await?
+        .rows_affected()
+        > 0)
+}
+
+/// Tables with a row per user or more, everything `delete_user` removes.
+const USER_TABLES: [&str; 21] = [
+    "users",
+    "vacations",
+    "overrides",
+    "projects",
+    "aliases",
+    "calendars",
+    "calendar_occurrences",
+    "snapshots",
+    "evaluations",
+    "submissions",
+    "milestones",
+    "checklist",
+    "checklist_projects",
+    "journal",
+    "codes",
+    "sessions",
+    "week_projects",
+    "identities",
+    "oidc_links",
+    "api_tokens",
+    "events",
+];
+
+/// Whether an admin disabled the user.
+pub async fn disabled(username: &str) -> Result<bool, sqlx::Error> {
+    Ok(query("SELECT disabled FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&*POOL)
+        .await?
+        .is_some_and(|r| r.get::<i64, _>("disabled") != 0))
+}
+
+/// Disables or enables a user, disabling ends their sessions. `false` if there's no such user.
+pub async fn set_disabled(username: &str, disabled: bool) -> Result<bool, sqlx::Error> {
+    let updated = query("UPDATE users SET disabled = ? WHERE username = ?")
+        .bind(disabled)
+        .bind(username)
+        .execute(&*POOL)
+        .await?
+        .rows_affected();
+    if disabled {
+        query("DELETE FROM sessions WHERE username = ?")
+            .bind(username)
+            .execute(&*POOL)
+            .await?;
+    }
+    Ok(updated > 0)
+}
+
+pub async fn role(username: &str) -> Result<Role, sqlx::Error> {
+    Ok(query("SELECT role FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&*POOL)
+        .await?
+        .map_or(Role::User, |r| Role::from_i64(r.get("role"))))
+}
+
+/// `false` if there's no such user.
+pub async fn set_role(username: &str, role: Role) -> Result<bool, sqlx::Error> {
+    Ok(query("UPDATE users SET role = ? WHERE username = ?")
+        .bind(role.to_i64())
+        .bind(username)
+        .execute(&*POOL)
+        .await?
+        .rows_affected()
+        > 0)
+}
+
+/// Records how a reminder or a request for a user went, for the admin page.
+pub async fn log_event(kind: &str, username: &str, ok: bool, detail: &str) {
+    if let Err(e) = query("INSERT INTO events (time, kind, username, ok, detail) VALUES (?, ?, ?, ?, ?)")
+        .bind(Utc::now().naive_utc().to_string())
+        .bind(kind)
+        .bind(username)
+        .bind(ok)
+        .bind(detail)
+        .execute(&*POOL)
+        .await
+    {
+        eprintln!("{}", e);
+    }
+}
+
+/// Removes everything stored about a user, returns the number of rows deleted.
+pub async fn delete_user(username: &str) -> Result<u64, sqlx::Error> {
+    let mut tx = POOL.begin().await?;
+    let mut deleted = 0;
+    for table in USER_TABLES {
+        deleted += query(&format!(r#"DELETE FROM "{}" WHERE username = ?"#, table))
+            .bind(username)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+/// A random secret for URLs.
+pub fn new_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// Adds a column to an existing table, databases created before the column existed get migrated this way.
+async fn add_column(table: &str, column: &str, definition: &str) {
+    match query(&format!(r#"ALTER TABLE "{}" ADD COLUMN "{}" {}"#, table, column, definition))
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => (),
+        Err(e) if e.to_string().contains("duplicate column name") => (),
+        Err(e) => panic!("{}", e),
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "ssr")]
+
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use sqlx::{query, Row};
+
+use crate::{
+    db::{settings_from_row, POOL},
+    ical,
+};
+
+/// Serves `/feed/{token}.ics`, the token identifies the user so calendar apps can subscribe without logging in.
+pub async fn feed(Path(file): Path<String>) -> Response {
+    let token = file.strip_suffix(".ics").unwrap_or(&file);
+    let row = match query("SELECT * FROM users WHERE feed_token = ? AND feed_token != '' AND disabled = 0")
+        .bind(token)
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Unknown feed").into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let settings = match settings_from_row(&row) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let username: String = row.get("username");
+
+    (
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        ical::feed(&username, &settings, Utc::now().naive_utc()),
+    )
+        .into_response()
+}
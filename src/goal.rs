@@ -0,0 +1,94 @@
+use chrono::{Duration, NaiveDate};
+
+use crate::datatypes::{GoalStatus, LongGoal};
+
+impl LongGoal {
+    /// Seconds that should be done by the end of `date` if the goal is worked on evenly.
+    pub fn expected(&self, date: NaiveDate) -> i64 {
+        let total = (self.end - self.start).num_days() + 1;
+        let passed = ((date - self.start).num_days() + 1).clamp(0, total);
+        self.hours as i64 * 60 * 60 * passed / total
+    }
+
+    /// Seconds per week still needed from `today` on, all that's left in the final week.
+    pub fn required_rate(&self, done: i64, today: NaiveDate) -> i64 {
+        let left = (self.hours as i64 * 60 * 60 - done).max(0);
+        let days = (self.end - today).num_days() + 1;
+        left * 7 / days.max(7)
+    }
+
+    /// Progress from the `daily` seconds, today only counts toward `done` since it isn't over yet.
+    pub fn status(&self, daily: &[(NaiveDate, i64)], today: NaiveDate) -> GoalStatus {
+        let in_goal = |d: &NaiveDate| self.start <= *d && *d <= self.end;
+        let done: i64 = daily.iter().filter(|d| in_goal(&d.0)).map(|d| d.1).sum();
+
+        let mut remaining = Vec::new();
+        let mut left = self.hours as i64 * 60 * 60;
+        let mut day = self.start;
+        while day <= self.end.min(today) {
+            left -= daily.iter().filter(|d| d.0 == day).map(|d| d.1).sum::<i64>();
+            remaining.push((day, left));
+            day += Duration::days(1);
+        }
+
+        GoalStatus {
+            goal: self.clone(),
+            done,
+            required: self.required_rate(done, today),
+            behind: (self.expected(today - Duration::days(1)) - done).max(0),
+            remaining,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal() -> LongGoal {
+        LongGoal {
+            hours: 60,
+            project: "siege".to_string(),
+            start: NaiveDate::from_ymd_opt(2025, 9, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 10, 12).unwrap(),
+        }
+    }
+
+    #[test]
+    fn even_progress() {
+        let g = goal();
+        // 42 days
+        assert_eq!(g.expected(g.start - Duration::days(1)), 0);
+        assert_eq!(g.expected(g.start + Duration::days(20)), 30 * 60 * 60);
+        assert_eq!(g.expected(g.end + Duration::days(5)), 60 * 60 * 60);
+    }
+
+    #[test]
+    fn required_rate() {
+        let g = goal();
+        // 30h left over three weeks
+        let today = g.end - Duration::days(20);
+        assert_eq!(g.required_rate(30 * 60 * 60, today), 10 * 60 * 60);
+        // the last days still need everything that's left
+        assert_eq!(g.required_rate(55 * 60 * 60, g.end), 5 * 60 * 60);
+        assert_eq!(g.required_rate(55 * 60 * 60, g.end + Duration::days(1)), 5 * 60 * 60);
+        assert_eq!(g.required_rate(70 * 60 * 60, today), 0);
+    }
+
+    #[test]
+    fn burndown_and_behind() {
+        let g = goal();
+        let daily: Vec<(NaiveDate, i64)> = (0..7)
+            .map(|i| (g.start + Duration::days(i), 60 * 60))
+            .chain([(g.start - Duration::days(1), 5 * 60 * 60)])
+            .collect();
+        let today = g.start + Duration::days(10);
+        let status = g.status(&daily, today);
+        assert_eq!(status.done, 7 * 60 * 60);
+        assert_eq!(status.remaining.len(), 11);
+        assert_eq!(status.remaining[0].1, 59 * 60 * 60);
+        assert_eq!(status.remaining[10].1, 53 * 60 * 60);
+        // 10 days should be 10/42 of 60h
+        assert_eq!(status.behind, 60 * 60 * 60 * 10 / 42 - 7 * 60 * 60);
+    }
+}
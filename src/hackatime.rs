@@ -0,0 +1,96 @@
+#![cfg(feature = "ssr")]
+
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+use crate::db::log_event;
+
+/// Fetches a users stats between `start` and `end` (now if `None`), errors are readable messages.
+pub async fn stats(
+    username: &str,
+    features: &str,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Value, String> {
+    request(username, params(features, start, end)).await
+}
+
+/// Like `stats` but only for one Hackatime project.
+pub async fn project_stats(
+    username: &str,
+    project: &str,
+    features: &str,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> Result<Value, String> {
+    let mut params = params(features, start, end);
+    params.push(("filter_by_project", project.to_string()));
+    request(username, params).await
+}
+
+/// Whether Hackatime knows `username`, errors are readable messages.
+pub async fn exists(username: &str) -> Result<bool, String> {
+    match fetch(username, params("projects", Utc::now(), None)).await {
+        Ok(_) => Ok(true),
+        Err(e) if e == "Username not found" => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn params(
+    features: &str,
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        ("features", features.to_string()),
+        ("start_date", start.to_rfc3339()),
+    ];
+    if let Some(end) = end {
+        params.push(("end_date", end.to_rfc3339()));
+    }
+    params
+}
+
+async fn request(username: &str, params: Vec<(&'static str, String)>) -> Result<Value, String> {
+    let result = fetch(username, params).await;
+    log_event(
+        "hackatime",
+        username,
+        result.is_ok(),
+        result.as_ref().err().map_or("", |e| e),
+    )
+    .await;
+    result
+}
+
+async fn fetch(username: &str, params: Vec<(&'static str, String)>) -> Result<Value, String> {
+    let client = Client::new();
+    let res = match client
+        .get(format!(
+            "https://hackatime.hackclub.com/api/v1/users/{}/stats",
+            username
+        ))
+        .query(&params)
+        .send()
+        .await
+    {
+        Ok(res) => res,
+        Err(e) => return Err(e.to_string()),
+    };
+    if res.status() == StatusCode::NOT_FOUND {
+        return Err("Username not found".to_string());
+    } else if !res.status().is_success() {
+        return Err(res
+            .status()
+            .canonical_reason()
+            .unwrap_or_else(|| "Unknown error")
+            .to_string());
+    }
+
+    match res.json().await {
+        Ok(d) => Ok(d),
+        Err(_) => Err("Failed to deserialize response data".to_string()),
+    }
+}
@@ -0,0 +1,783 @@
+use std::net::IpAddr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::{
+    datatypes::{Settings, Window},
+    schedule::{WEEK, WEEK_START},
+};
+
+/// A VEVENT, times are in the users local time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+    /// Reminders are skipped on days with a holiday.
+    pub holiday: bool,
+    /// Reminders during a busy event wait until it's over.
+    pub busy: bool,
+    pub rule: Option<Rule>,
+    pub exdates: Vec<NaiveDateTime>,
+}
+
+/// The supported subset of RRULE.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub freq: Freq,
+    pub interval: i32,
+    pub count: Option<usize>,
+    pub until: Option<NaiveDateTime>,
+    pub by_day: Vec<Weekday>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+}
+
+/// A single occurrence of an event, in UTC.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Occurrence {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub holiday: bool,
+    pub busy: bool,
+}
+
+/// Parses the events of an iCalendar file. Times without a timezone (or with a TZID) are taken
+/// as the users local time, `offset` is the users offset from UTC in seconds.
+pub fn parse(ics: &str, offset: i32) -> Vec<Event> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in ics.lines() {
+        if let Some(rest) = raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        lines.push(raw.to_string());
+    }
+
+    let mut events = Vec::new();
+    let mut props: Option<Vec<Property>> = None;
+    for line in lines {
+        let prop = match Property::parse(&line) {
+            Some(p) => p,
+            None => continue,
+        };
+        match (prop.name.as_str(), prop.value.as_str()) {
+            ("BEGIN", "VEVENT") => props = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = props.take().and_then(|p| event(&p, offset)) {
+                    events.push(event);
+                }
+            }
+            _ => {
+                if let Some(p) = props.as_mut() {
+                    p.push(prop);
+                }
+            }
+        }
+    }
+    events
+}
+
+/// All occurrences overlapping `from` to `to` (UTC).
+pub fn occurrences(
+    events: &[Event],
+    offset: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Vec<Occurrence> {
+    let offset = Duration::seconds(offset as i64);
+    let (from, to) = (from + offset, to + offset);
+    let mut out = Vec::new();
+    for e in events {
+        let length = e.end - e.start;
+        let mut push = |start: NaiveDateTime| {
+            if start < to && start + length > from && !e.exdates.contains(&start) {
+                out.push(Occurrence {
+                    start: start - offset,
+                    end: start + length - offset,
+                    holiday: e.holiday,
+                    busy: e.busy,
+                });
+            }
+        };
+        let rule = match &e.rule {
+            Some(r) => r,
+            None => {
+                push(e.start);
+                continue;
+            }
+        };
+
+        let step = match rule.freq {
+            Freq::Daily => Duration::days(rule.interval as i64),
+            Freq::Weekly => Duration::weeks(rule.interval as i64),
+        };
+        let mut n = 0;
+        'periods: for period in 0.. {
+            let base = e.start + step * period;
+            let starts = if rule.freq == Freq::Weekly && !rule.by_day.is_empty() {
+                let monday = base - Duration::days(base.weekday().num_days_from_monday() as i64);
+                let mut starts: Vec<NaiveDateTime> = rule
+                    .by_day
+                    .iter()
+                    .map(|d| monday + Duration::days(d.num_days_from_monday() as i64))
+                    .filter(|s| *s >= e.start)
+                    .collect();
+                starts.sort();
+                starts
+            } else {
+                vec![base]
+            };
+            for start in starts {
+                if rule.until.is_some_and(|u| start > u)
+                    || rule.count.is_some_and(|c| n >= c)
+                    || start >= to
+                {
+                    break 'periods;
+                }
+                n += 1;
+                push(start);
+            }
+        }
+    }
+    out
+}
+
+/// Whether a holiday covers any part of a local date.
+pub fn is_holiday(events: &[Event], offset: i32, date: NaiveDate) -> bool {
+    let from = date.and_hms_opt(0, 0, 0).unwrap() - Duration::seconds(offset as i64);
+    holiday_on(&occurrences(events, offset, from, from + Duration::days(1)), offset, date)
+}
+
+/// Whether one of `occurrences` is a holiday covering any part of a local date.
+pub fn holiday_on(occurrences: &[Occurrence], offset: i32, date: NaiveDate) -> bool {
+    let from = date.and_hms_opt(0, 0, 0).unwrap() - Duration::seconds(offset as i64);
+    let to = from + Duration::days(1);
+    occurrences
+        .iter()
+        .any(|o| o.holiday && o.start < to && o.end > from)
+}
+
+/// The first moment at or after `at` (UTC) that isn't during a busy event.
+pub fn first_free(events: &[Event], offset: i32, at: NaiveDateTime) -> NaiveDateTime {
+    free_at(&occurrences(events, offset, at, at + Duration::days(2)), at)
+}
+
+/// The first moment at or after `at` that isn't during one of the busy `occurrences`.
+pub fn free_at(occurrences: &[Occurrence], at: NaiveDateTime) -> NaiveDateTime {
+    let mut at = at;
+    while let Some(end) = occurrences
+        .iter()
+        .filter(|o| o.busy && o.start <= at && at < o.end)
+        .map(|o| o.end)
+        .max()
+    {
+        at = end;
+    }
+    at
+}
+
+/// A calendar of a users reminder slots and the weekly submission deadline, all repeating weekly.
+pub fn feed(username: &str, settings: &Settings, now: NaiveDateTime) -> String {
+    let monday = now.date().week(Weekday::Mon).first_day().and_hms_opt(0, 0, 0).unwrap();
+    let stamp = format_utc(now);
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//siege-reminder//schedule//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Siege reminders".to_string(),
+    ];
+    let days = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+    for (day, slot) in settings.slots().iter().enumerate() {
+        let measured = match slot.window {
+            Window::PreviousSlot => "since the previous reminder",
+            Window::LocalMidnight => "today",
+            Window::Rolling => "in the last 24 hours",
+            Window::WeekToDate => "this week",
+        };
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}-{}@siege-reminder", username, days[day].to_lowercase()),
+            format!("DTSTAMP:{}", stamp),
+            format!(
+                "DTSTART:{}",
+                format_utc(monday + Duration::minutes(slot.time.rem_euclid(WEEK)))
+            ),
+            "DURATION:PT15M".to_string(),
+            "RRULE:FREQ=WEEKLY".to_string(),
+            // reminders shouldn't show up as busy time
+            "TRANSP:TRANSPARENT".to_string(),
+            format!("SUMMARY:{} reminder", days[day]),
+            format!(
+                "DESCRIPTION:{}",
+                escape(&format!("Goal: {}h {}", settings.goal(day), measured))
+            ),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.extend([
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}-deadline@siege-reminder", username),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART:{}", format_utc(monday + Duration::minutes(WEEK_START))),
+        "DURATION:PT0S".to_string(),
+        "RRULE:FREQ=WEEKLY".to_string(),
+        "TRANSP:TRANSPARENT".to_string(),
+        "SUMMARY:Submission deadline".to_string(),
+        "DESCRIPTION:Submit this weeks project.".to_string(),
+        "BEGIN:VALARM".to_string(),
+        "ACTION:DISPLAY".to_string(),
+        "TRIGGER:-PT2H".to_string(),
+        "DESCRIPTION:Submission deadline in 2 hours".to_string(),
+        "END:VALARM".to_string(),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ]);
+    lines.join("\r\n") + "\r\n"
+}
+
+fn format_utc(time: NaiveDateTime) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Property> {
+        let mut quoted = false;
+        let split = line.char_indices().find(|(_, c)| {
+            if *c == '"' {
+                quoted = !quoted;
+            }
+            *c == ':' && !quoted
+        })?;
+        let (head, value) = (&line[..split.0], &line[split.0 + 1..]);
+        let mut head = head.split(';');
+        let name = head.next()?.to_uppercase();
+        let params = head
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.to_uppercase(), v.trim_matches('"').to_string()))
+            .collect();
+        Some(Property {
+            name,
+            params,
+            value: value.to_string(),
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|p| p.0 == name)
+            .map(|p| p.1.as_str())
+    }
+}
+
+fn event(props: &[Property], offset: i32) -> Option<Event> {
+    let get = |name: &str| props.iter().find(|p| p.name == name);
+
+    let start_prop = get("DTSTART")?;
+    let (start, all_day) = parse_time(&start_prop.value, start_prop.param("VALUE"), offset)?;
+    let end = match (get("DTEND"), get("DURATION")) {
+        (Some(p), _) => parse_time(&p.value, p.param("VALUE"), offset)?.0,
+        (None, Some(p)) => start + parse_duration(&p.value)?,
+        (None, None) if all_day => start + Duration::days(1),
+        (None, None) => start,
+    };
+
+    let summary = get("SUMMARY").map(|p| unescape(&p.value)).unwrap_or_default();
+    let categories = props
+        .iter()
+        .filter(|p| p.name == "CATEGORIES")
+        .map(|p| p.value.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(",");
+    let holiday = categories.contains("holiday")
+        || ["holiday", "vacation"]
+            .iter()
+            .any(|w| summary.to_lowercase().contains(w));
+    let transparent = get("TRANSP").is_some_and(|p| p.value.eq_ignore_ascii_case("TRANSPARENT"));
+    let cancelled = get("STATUS").is_some_and(|p| p.value.eq_ignore_ascii_case("CANCELLED"));
+    if cancelled {
+        return None;
+    }
+
+    let exdates = props
+        .iter()
+        .filter(|p| p.name == "EXDATE")
+        .flat_map(|p| {
+            p.value
+                .split(',')
+                .filter_map(|v| parse_time(v, p.param("VALUE"), offset))
+                .map(|t| t.0)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Some(Event {
+        summary,
+        start,
+        end,
+        all_day,
+        holiday,
+        busy: !holiday && !all_day && !transparent,
+        rule: get("RRULE").and_then(|p| parse_rule(&p.value, offset)),
+        exdates,
+    })
+}
+
+/// Parses DATE and DATE-TIME values into local time, the bool is true for dates.
+fn parse_time(value: &str, kind: Option<&str>, offset: i32) -> Option<(NaiveDateTime, bool)> {
+    if kind == Some("DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(0, 0, 0)?, true));
+    }
+    match value.strip_suffix('Z') {
+        Some(utc) => {
+            let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            Some((time + Duration::seconds(offset as i64), false))
+        }
+        None => Some((
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+            false,
+        )),
+    }
+}
+
+/// Parses durations like `PT1H30M` or `P1D`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut time = false;
+    for c in value.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => time = true,
+            _ => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match (c, time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+fn parse_rule(value: &str, offset: i32) -> Option<Rule> {
+    let mut rule = Rule {
+        freq: Freq::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: Vec::new(),
+    };
+    for part in value.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                rule.freq = match value.to_uppercase().as_str() {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    // other frequencies are treated like single events
+                    _ => return None,
+                }
+            }
+            "INTERVAL" => rule.interval = value.parse().ok().filter(|i| *i > 0)?,
+            "COUNT" => rule.count = value.parse().ok(),
+            "UNTIL" => rule.until = parse_time(value, None, offset).map(|t| t.0),
+            "BYDAY" => {
+                rule.by_day = value
+                    .split(',')
+                    // the day is the end of entries like 1MO or -1FR
+                    .filter_map(|d| {
+                        [
+                            ("MO", Weekday::Mon),
+                            ("TU", Weekday::Tue),
+                            ("WE", Weekday::Wed),
+                            ("TH", Weekday::Thu),
+                            ("FR", Weekday::Fri),
+                            ("SA", Weekday::Sat),
+                            ("SU", Weekday::Sun),
+                        ]
+                        .into_iter()
+                        .find(|(name, _)| d.ends_with(name))
+                        .map(|(_, day)| day)
+                    })
+                    .collect()
+            }
+            _ => (),
+        }
+    }
+    Some(rule)
+}
+
+/// Redirects followed when fetching a calendar.
+#[cfg(feature = "ssr")]
+const REDIRECTS: usize = 5;
+/// Biggest calendar that can be fetched or uploaded, in bytes.
+pub const MAX_CALENDAR_BYTES: usize = 2 * 1024 * 1024;
+
+/// Whether `content` looks like an iCalendar file.
+pub fn is_calendar(content: &str) -> bool {
+    content.trim_start_matches('\u{feff}').trim_start().starts_with("BEGIN:VCALENDAR")
+}
+
+/// Whether a calendar may be fetched from `ip`, so URLs can't reach the server itself or the
+/// network it's in.
+pub fn public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // shared address space of carrier grade NATs
+                || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => public(IpAddr::V4(v4)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // unique local and link local
+                    || ip.segments()[0] & 0xfe00 == 0xfc00
+                    || ip.segments()[0] & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Loads a calendar from an http(s) or webcal URL, or a file URL inside `CALENDAR_DIR`.
+#[cfg(feature = "ssr")]
+pub async fn fetch(url: &str) -> Result<String, String> {
+    let content = if let Some(path) = url.strip_prefix("file://") {
+        read_file(path).await?
+    } else {
+        let url = match url.strip_prefix("webcal://") {
+            Some(rest) => format!("https://{}", rest),
+            None if url.starts_with("http://") || url.starts_with("https://") => url.to_string(),
+            None => return Err("Use an http(s) or webcal URL".to_string()),
+        };
+        download(&url).await?
+    };
+    if !is_calendar(&content) {
+        return Err("Not an iCalendar file".to_string());
+    }
+    Ok(content)
+}
+
+/// Reads a file URL, only files in the directory the operator set as `CALENDAR_DIR` can be read.
+/// Errors don't tell whether the file exists.
+#[cfg(feature = "ssr")]
+async fn read_file(path: &str) -> Result<String, String> {
+    let dir = match std::env::var("CALENDAR_DIR") {
+        Ok(d) if !d.is_empty() => d,
+        _ => return Err("File URLs aren't enabled on this server".to_string()),
+    };
+    let unreadable = || "Can't read this file".to_string();
+    let dir = tokio::fs::canonicalize(dir).await.map_err(|_| unreadable())?;
+    let path = tokio::fs::canonicalize(path)
+        .await
+        .map_err(|_| unreadable())?;
+    if !path.starts_with(&dir) {
+        return Err(unreadable());
+    }
+    let size = tokio::fs::metadata(&path)
+        .await
+        .map_err(|_| unreadable())?
+        .len();
+    if size > MAX_CALENDAR_BYTES as u64 {
+        return Err(too_big());
+    }
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|_| unreadable())
+}
+
+#[cfg(feature = "ssr")]
+fn too_big() -> String {
+    format!("Calendars can be up to {} MB", MAX_CALENDAR_BYTES / 1024 / 1024)
+}
+
+/// Downloads a calendar, every URL including redirects has to point at a public address. The
+/// request goes to the address that was checked so DNS can't change it in between.
+#[cfg(feature = "ssr")]
+async fn download(url: &str) -> Result<String, String> {
+    let mut url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    for _ in 0..=REDIRECTS {
+        let host = url.host_str().ok_or("The URL has no host")?.to_string();
+        let port = url.port_or_known_default().ok_or("The URL has no port")?;
+        let addr = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+            .await
+            .map_err(|e| e.to_string())?
+            .find(|a| public(a.ip()))
+            .ok_or("Calendars can't be fetched from private addresses")?;
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve(&host, addr)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut res = client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if res.status().is_redirection() {
+            let location = res
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|l| l.to_str().ok())
+                .ok_or("Redirect without a location")?;
+            url = url.join(location).map_err(|e| e.to_string())?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err("Redirected to something that isn't http(s)".to_string());
+            }
+            continue;
+        }
+        if !res.status().is_success() {
+            return Err(res
+                .status()
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string());
+        }
+        if res
+            .content_length()
+            .is_some_and(|l| l > MAX_CALENDAR_BYTES as u64)
+        {
+            return Err(too_big());
+        }
+        // the length can be missing or wrong, so the body is counted too
+        let mut body = Vec::new();
+        while let Some(chunk) = res.chunk().await.map_err(|e| e.to_string())? {
+            if body.len() + chunk.len() > MAX_CALENDAR_BYTES {
+                return Err(too_big());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        return String::from_utf8(body).map_err(|_| "Not an iCalendar file".to_string());
+    }
+    Err("Too many redirects".to_string())
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../fixtures/timetable.ics");
+    // CEST
+    const OFFSET: i32 = 2 * 60 * 60;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+            - Duration::seconds(OFFSET as i64)
+    }
+
+    #[test]
+    fn parses_the_fixture() {
+        let events = parse(FIXTURE, OFFSET);
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[1].summary, "Robotics club, room 4");
+        assert_eq!(events[3].summary, "Parent teacher call");
+        assert!(events[2].holiday && events[2].all_day && !events[2].busy);
+        assert!(!events[4].busy);
+        assert_eq!(events[4].end - events[4].start, Duration::hours(1));
+        let rule = events[0].rule.as_ref().unwrap();
+        assert_eq!(rule.freq, Freq::Weekly);
+        assert_eq!(rule.by_day.len(), 5);
+    }
+
+    #[test]
+    fn holidays() {
+        let events = parse(FIXTURE, OFFSET);
+        let date = |d| NaiveDate::from_ymd_opt(2025, 10, d).unwrap();
+        assert!(!is_holiday(&events, OFFSET, date(26)));
+        assert!(is_holiday(&events, OFFSET, date(27)));
+        assert!(is_holiday(&events, OFFSET, date(31)));
+        assert!(!is_holiday(&events, OFFSET, NaiveDate::from_ymd_opt(2025, 11, 1).unwrap()));
+    }
+
+    #[test]
+    fn busy_pushes_to_the_end() {
+        let events = parse(FIXTURE, OFFSET);
+        assert_eq!(
+            first_free(&events, OFFSET, local(2025, 10, 13, 9, 0)),
+            local(2025, 10, 13, 13, 0)
+        );
+        // school is directly followed by the club every other wednesday
+        assert_eq!(
+            first_free(&events, OFFSET, local(2025, 10, 8, 10, 0)),
+            local(2025, 10, 8, 15, 0)
+        );
+        assert_eq!(
+            first_free(&events, OFFSET, local(2025, 10, 15, 10, 0)),
+            local(2025, 10, 15, 13, 0)
+        );
+        assert_eq!(
+            first_free(&events, OFFSET, local(2025, 10, 22, 10, 0)),
+            local(2025, 10, 22, 15, 0)
+        );
+        // the club only happens three times
+        assert_eq!(
+            first_free(&events, OFFSET, local(2025, 11, 19, 10, 0)),
+            local(2025, 11, 19, 13, 0)
+        );
+    }
+
+    #[test]
+    fn free_times_stay() {
+        let events = parse(FIXTURE, OFFSET);
+        for at in [
+            // weekend
+            local(2025, 10, 11, 9, 0),
+            // excluded date
+            local(2025, 10, 10, 9, 0),
+            // transparent event
+            local(2025, 10, 9, 18, 30),
+            // after the timetable ended
+            local(2025, 12, 22, 9, 0),
+        ] {
+            assert_eq!(first_free(&events, OFFSET, at), at);
+        }
+    }
+
+    #[test]
+    fn utc_times() {
+        let events = parse(FIXTURE, OFFSET);
+        let at = NaiveDate::from_ymd_opt(2025, 10, 7)
+            .unwrap()
+            .and_hms_opt(16, 30, 0)
+            .unwrap();
+        assert_eq!(
+            first_free(&events, OFFSET, at),
+            at + Duration::minutes(30)
+        );
+    }
+
+    #[test]
+    fn feed_round_trip() {
+        use crate::datatypes::Slot;
+        use chrono::Timelike;
+
+        let slot = |time: i64, window| Slot { time, goal: 2, window };
+        let settings = Settings {
+            monday: slot(18 * 60, Window::PreviousSlot),
+            tuesday: slot(24 * 60 + 18 * 60, Window::PreviousSlot),
+            wednesday: slot(2 * 24 * 60 + 18 * 60, Window::PreviousSlot),
+            thursday: slot(3 * 24 * 60 + 18 * 60, Window::PreviousSlot),
+            friday: slot(4 * 24 * 60 + 18 * 60, Window::PreviousSlot),
+            saturday: slot(5 * 24 * 60 + 18 * 60, Window::PreviousSlot),
+            // sunday 23:00 at UTC+2
+            sunday: slot(-60, Window::WeekToDate),
+            offset: OFFSET,
+        };
+        let now = NaiveDate::from_ymd_opt(2025, 10, 8)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let ics = feed("someone", &settings, now);
+        assert!(ics.contains("DESCRIPTION:Goal: 14h this week"));
+        assert!(ics.contains("TRIGGER:-PT2H"));
+
+        let events = parse(&ics, OFFSET);
+        assert_eq!(events.len(), 8);
+        assert!(events.iter().all(|e| !e.busy && !e.holiday));
+        // the schedule repeats every week
+        let next_week = now + Duration::weeks(3);
+        let week = occurrences(&events, OFFSET, next_week, next_week + Duration::weeks(1));
+        assert_eq!(week.len(), 8);
+        assert!(week.iter().any(|o| o.start.weekday() == Weekday::Sun
+            && o.start.hour() == 23));
+        assert!(week.iter().any(|o| o.start.weekday() == Weekday::Mon
+            && o.start.hour() == 4));
+    }
+
+    #[test]
+    fn by_day() {
+        let rule = parse_rule("FREQ=WEEKLY;BYDAY=MO,1TU,-1FR", 0).unwrap();
+        assert_eq!(rule.by_day, vec![Weekday::Mon, Weekday::Tue, Weekday::Fri]);
+        // multibyte characters don't split a char in half
+        for value in ["FREQ=WEEKLY;BYDAY=1ÄO", "FREQ=WEEKLY;BYDAY=Ä", "FREQ=WEEKLY;BYDAY=ÄMO"] {
+            let rule = parse_rule(value, 0).unwrap();
+            assert!(rule.by_day.len() <= 1);
+        }
+    }
+
+    #[test]
+    fn private_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["1.1.1.1", "140.82.112.3", "2606:4700::1111"] {
+            assert!(public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn calendar_content() {
+        assert!(is_calendar(FIXTURE));
+        assert!(is_calendar("\u{feff}BEGIN:VCALENDAR\r\nEND:VCALENDAR"));
+        assert!(!is_calendar("<html>BEGIN:VCALENDAR</html>"));
+        assert!(!is_calendar(""));
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::{datatypes::JournalEntry, schedule::hm};
+#[cfg(feature = "ssr")]
+use crate::{
+    datatypes::Alias,
+    hackatime::{project_stats, stats},
+    projects::{all_projects, resolve, totals},
+};
+
+/// Files listed in a draft at most, the rest is summed up.
+const DRAFT_FILES: usize = 5;
+
+/// A journal entry to start from, built from a days time, languages and files (seconds each).
+pub fn draft(
+    project: &str,
+    seconds: i64,
+    languages: &[(String, i64)],
+    files: &[(String, i64)],
+) -> String {
+    if seconds == 0 {
+        return format!("No time on {} today.\n", project);
+    }
+    let mut out = format!("Worked {} on {}.\n", hm(seconds), project);
+    let languages = most_first(languages);
+    if !languages.is_empty() {
+        out.push_str(&format!(
+            "Languages: {}\n",
+            languages
+                .iter()
+                .map(|(name, sec)| format!("{} ({})", name, hm(*sec)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    let files = most_first(files);
+    if !files.is_empty() {
+        out.push_str(&format!(
+            "Files: {}",
+            files
+                .iter()
+                .take(DRAFT_FILES)
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        if files.len() > DRAFT_FILES {
+            out.push_str(&format!(" and {} more", files.len() - DRAFT_FILES));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn most_first(items: &[(String, i64)]) -> Vec<&(String, i64)> {
+    let mut items = items.iter().filter(|i| i.1 > 0).collect::<Vec<_>>();
+    items.sort_by_key(|i| -i.1);
+    items
+}
+
+/// Fetches a days Hackatime data for `project` and drafts an entry from it, `offset` is the users
+/// UTC offset in seconds.
+#[cfg(feature = "ssr")]
+pub async fn fetch_draft(
+    username: &str,
+    project: &str,
+    day: NaiveDate,
+    offset: i32,
+    aliases: &[Alias],
+) -> Result<String, String> {
+    let start = (day.and_hms_opt(0, 0, 0).unwrap() - Duration::seconds(offset as i64)).and_utc();
+    let end = start + Duration::days(1);
+    let tracked = [project.to_string()];
+    let data = stats(username, "projects", start, Some(end)).await?;
+    let names: Vec<(String, i64)> = all_projects(&data)
+        .into_iter()
+        .filter(|(name, _)| resolve(name, &tracked, aliases).is_some())
+        .collect();
+
+    let mut languages: Vec<(String, i64)> = Vec::new();
+    let mut files: Vec<(String, i64)> = Vec::new();
+    for (name, _) in &names {
+        // not every provider knows about files
+        let data = match project_stats(username, name, "languages,files", start, Some(end)).await {
+            Ok(d) => d,
+            Err(_) => project_stats(username, name, "languages", start, Some(end)).await?,
+        };
+        for (list, feature) in [(&mut languages, "languages"), (&mut files, "files")] {
+            for (item, sec) in totals(&data, feature) {
+                match list.iter_mut().find(|l| l.0 == item) {
+                    Some(l) => l.1 += sec,
+                    None => list.push((item, sec)),
+                }
+            }
+        }
+    }
+    Ok(draft(project, names.iter().map(|n| n.1).sum(), &languages, &files))
+}
+
+/// A weeks entries as Markdown, a section per day and a subsection per project.
+pub fn markdown(week: NaiveDate, entries: &[JournalEntry]) -> String {
+    let mut out = format!(
+        "# Devlog {} to {}\n",
+        week.format("%d.%m."),
+        (week + Duration::days(6)).format("%d.%m.%Y")
+    );
+    let mut entries = entries
+        .iter()
+        .filter(|e| week <= e.day && e.day < week + Duration::days(7) && !e.text.trim().is_empty())
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| (a.day, &a.project).cmp(&(b.day, &b.project)));
+    let mut day = None;
+    for e in entries {
+        if day != Some(e.day) {
+            day = Some(e.day);
+            out.push_str(&format!("\n## {:?} {}\n", e.day.weekday(), e.day.format("%d.%m.")));
+        }
+        out.push_str(&format!("\n### {}\n\n{}\n", e.project, e.text.trim()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(name: &str, sec: i64) -> (String, i64) {
+        (name.to_string(), sec)
+    }
+
+    #[test]
+    fn drafts() {
+        assert_eq!(draft("siege", 0, &[], &[]), "No time on siege today.\n");
+        assert_eq!(
+            draft("siege", 4800, &[s("CSS", 900), s("Rust", 3900)], &[]),
+            "Worked 1h 20m on siege.\nLanguages: Rust (1h 5m), CSS (15m)\n"
+        );
+        let files: Vec<_> = (1..=7).map(|i| s(&format!("f{}.rs", i), i * 60)).collect();
+        assert_eq!(
+            draft("siege", 3600, &[], &files),
+            "Worked 1h on siege.\nFiles: f7.rs, f6.rs, f5.rs, f4.rs, f3.rs and 2 more\n"
+        );
+    }
+
+    #[test]
+    fn markdown_by_day() {
+        let week = NaiveDate::from_ymd_opt(2025, 9, 8).unwrap();
+        let entry = |day: u32, project: &str, text: &str| JournalEntry {
+            project: project.to_string(),
+            day: NaiveDate::from_ymd_opt(2025, 9, day).unwrap(),
+            text: text.to_string(),
+        };
+        let entries = [
+            entry(10, "siege", "Added charts."),
+            entry(8, "siege", "Started.\n"),
+            entry(8, "api", "Set up the repo."),
+            entry(9, "siege", " "),
+            entry(15, "siege", "Next week."),
+        ];
+        assert_eq!(
+            markdown(week, &entries),
+            "# Devlog 08.09. to 14.09.2025\n\
+             \n## Mon 08.09.\n\n### api\n\nSet up the repo.\n\n### siege\n\nStarted.\n\
+             \n## Wed 10.09.\n\n### siege\n\nAdded charts.\n"
+        );
+    }
+}
@@ -1,8 +1,22 @@
+pub mod admin;
+pub mod actions;
+pub mod api;
 pub mod app;
+pub mod auth;
+pub mod carry;
 pub mod datatypes;
 pub mod db;
+pub mod feed;
+pub mod goal;
+pub mod hackatime;
+pub mod ical;
+pub mod journal;
+pub mod oidc;
 pub mod pages;
+pub mod projects;
+pub mod schedule;
 pub mod server;
+pub mod snapshots;
 pub mod notifications;
 
 #[cfg(feature = "hydrate")]
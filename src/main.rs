@@ -3,13 +3,27 @@
 async fn main() {
     use std::env;
 
-    use axum::Router;
+    use axum::{
+        routing::{get, post},
+        Router,
+    };
     use leptos::prelude::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
-    use siege_reminder::{app::*, db::prep_db, notifications::notifications};
+    use siege_reminder::{
+        actions::{milestone_done, ship}, admin, api, app::*, db::prep_db, feed::feed,
+        notifications::notifications, oidc,
+    };
     use tokio::spawn;
 
     prep_db().await;
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("admin") {
+        if let Err(e) = admin::run(&args[1..]).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     spawn(notifications());
 
     let conf = get_configuration(None).unwrap();
@@ -18,6 +32,12 @@ async fn main() {
     let options_for_routes = leptos_options.clone();
 
     let app = Router::new()
+        .route("/feed/:file", get(feed))
+        .route("/actions/milestone/:token/done", post(milestone_done))
+        .route("/actions/ship/:token", post(ship))
+        .route("/auth/oidc/login", get(oidc::login))
+        .route("/auth/oidc/callback", get(oidc::callback))
+        .nest("/api/v1", api::router())
         .leptos_routes(&leptos_options, routes, move || {
             shell(options_for_routes.clone())
         })
@@ -1,58 +1,57 @@
 #![cfg(feature = "ssr")]
 
 use axum::http::HeaderMap;
-use chrono::{Datelike, Utc};
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, Utc};
 use reqwest::Client;
-use serde_json::Value;
-use sqlx::{query, Row};
-use std::time::Duration;
-use tokio::time::interval;
+use sqlx::{query, sqlite::SqliteRow, Row};
+use std::{sync::Mutex, time::Duration};
+use tokio::{spawn, time::interval};
 
-use crate::db::POOL;
+use crate::{
+    actions::{action, submit_link, view},
+    carry::{adjusted_goal, adjusted_weekly_goal, settle_week},
+    datatypes::{Alias, Carry, CarryMode, GoalStatus, ProjectCarry, Settings, Window},
+    db::{
+        action_token, aliases, calendar_occurrences, carry_from_row, checklist, create_checklists,
+        deadline_alerts_from_row, log_event, long_goal_from_row, milestones, ntfy_topic, on_vacation,
+        overrides_between, project_carry_from_row, save_occurrences, save_week_projects,
+        settings_from_row, shipped, snapshots, submission, tracked_projects, week_projects, POOL,
+    },
+    hackatime::stats,
+    ical::{fetch, free_at, holiday_on},
+    projects::{all_projects, daily_totals, get_projects, untracked_projects},
+    schedule::{week_of, WEEK, WEEK_START},
+    snapshots::record_snapshots,
+};
+
+/// Milestone reminders go out at this local time, in minutes after midnight.
+const MILESTONE_MINUTE: i64 = 10 * 60;
+/// Busy events push a reminder back by up to this many minutes.
+const PUSH_MINUTES: i64 = 2 * 24 * 60;
+
+/// The last minute the scheduler went through and when it was done, for the admin page.
+pub static LAST_RUN: Mutex<Option<(NaiveDateTime, NaiveDateTime)>> = Mutex::new(None);
+
+/// Monday 00:00 UTC of the week `at` is in and the minutes since then, the schedulers clock.
+pub fn week_minute(at: NaiveDateTime) -> (NaiveDateTime, i64) {
+    let monday = (at.date() - chrono::Duration::days(at.weekday().num_days_from_monday() as i64))
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    (monday, (at - monday).num_minutes())
+}
 
 pub async fn notifications() {
     let mut interval = interval(Duration::from_secs(1));
     let mut last = 0;
+    // calendars imported before occurrences were stored have none yet
+    spawn(refresh_calendars());
+    // old events are pruned every hour, start with that instead of waiting for the first
+    prune_events().await;
     loop {
-        let monday = Utc::now()
-            .date_naive()
-            .checked_sub_signed(chrono::Duration::days(
-                Utc::now().weekday().num_days_from_monday() as i64,
-            ))
-            .unwrap()
-            .and_hms_opt(0, 0, 0)
-            .unwrap();
-        let time_passed = Utc::now()
-            .naive_utc()
-            .signed_duration_since(monday)
-            .num_minutes();
+        let (monday, time_passed) = week_minute(Utc::now().naive_utc());
         if time_passed != last {
             last = time_passed;
-            let rows = match query("SELECT username, project, 
-                CASE
-                    WHEN monday = ?1 THEN monday_goal
-                    WHEN tuesday = ?1 THEN tuesday_goal
-                    WHEN wednesday = ?1 THEN wednesday_goal
-                    WHEN thursday = ?1 THEN thursday_goal
-                    WHEN friday = ?1 THEN friday_goal
-                    WHEN saturday = ?1 THEN saturday_goal
-                    WHEN sunday = ?1 THEN (monday_goal + tuesday_goal + wednesday_goal + thursday_goal + friday_goal + saturday_goal + sunday_goal)
-                END AS goal,
-                CASE
-                    WHEN monday = ?1 THEN 0
-                    WHEN tuesday = ?1 THEN monday
-                    WHEN wednesday = ?1 THEN thursday
-                    WHEN thursday = ?1 THEN wednesday
-                    WHEN friday = ?1 THEN thursday
-                    WHEN saturday = ?1 THEN friday
-                    WHEN sunday = ?1 THEN saturday
-                END AS last_time,
-                CASE
-                    WHEN sunday = ?1 THEN 1
-                    ELSE 0
-                END AS sunday
-                FROM users WHERE ?1 IN (monday, tuesday, wednesday, thursday, friday, saturday, sunday)"
-            ).bind(time_passed).fetch_all(&*POOL).await {
+            let rows = match query("SELECT * FROM users WHERE disabled = 0").fetch_all(&*POOL).await {
                 Ok(r) => r,
                 Err(e) => {
                     eprintln!("{}", e);
@@ -60,116 +59,75 @@ pub async fn notifications() {
                 },
             };
             for i in rows {
+                let settings = match settings_from_row(&i) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        continue;
+                    }
+                };
                 let username: String = i.get("username");
-                let project: String = i.get("project");
-                if project == "".to_string() {
-                    let mut headers = HeaderMap::new();
-                    headers.append("Title", "Set your project.".parse().unwrap());
-                    let client = Client::new();
-                    client
-                        .post(format!("https://ntfy.tim.hackclub.app/{}", username))
-                        .headers(headers)
-                        .body("You haven't set a project for this week.")
-                        .send()
-                        .await
-                        .unwrap();
-                    return;
+                let now = monday + chrono::Duration::minutes(time_passed);
+                if settings.local_minute(now) == MILESTONE_MINUTE {
+                    milestone_reminders(&username, settings.local_date(now)).await;
                 }
-                let goal: i64 = i.get("goal");
-
-                let client = Client::new();
-                let sunday: u8 = i.get("sunday");
-                let res = match if sunday == 0 {
-                    client
-                        .get(format!(
-                            "https://hackatime.hackclub.com/api/v1/users/{}/stats?features=projects&start_date={}",
-                            username,
-                            monday.checked_add_signed(chrono::Duration::minutes(i.get("last_time"))).unwrap().and_utc().to_rfc3339()
-                        ))
-                        .send()
-                        .await
-                } else {
-                    let today = Utc::now().date_naive();
-                    let week_start = today
-                        - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
-                    client
-                        .get(format!(
-                            "https://hackatime.hackclub.com/api/v1/users/{}/stats?features=projects&start_date={}",
-                            username,
-                            week_start.and_hms_opt(4, 0, 0).unwrap().and_utc().to_rfc3339()
-                        ))
-                        .send()
-                        .await
-                } {
-                    Ok(res) => res,
-                    Err(_) => continue,
-                };
-
-                if !res.status().is_success() {
-                    continue;
+                if i.get::<Option<i64>, _>("journal_prompt") == Some(settings.local_minute(now)) {
+                    send_with_actions(
+                        &username,
+                        "How did it go today?",
+                        Some("memo"),
+                        "Write down what you did for your devlog, there's a draft from today's Hackatime data.",
+                        view("Write", "/journal").as_deref(),
+                    )
+                    .await;
                 }
-                let data: Value = match res.json().await {
-                    Ok(d) => d,
-                    Err(_) => continue,
-                };
-
-                let seconds_spend = match get_project(data, project.clone()) {
-                    Some(s) => s,
-                    _ => 0,
-                };
-
-                let sec_over_goal = seconds_spend - goal * 60 * 60;
-
-                let mut headers = HeaderMap::new();
-
-                let msg = if sunday == 0 {
-                    if sec_over_goal >= 0 {
-                        headers.append("Title", "Good Job!".parse().unwrap());
-                        headers.append("Tags", "tada".parse().unwrap());
-                        format!(
-                            "You worked for {} more than your goal was.",
-                            sec_to_hms(sec_over_goal)
-                        )
-                    } else {
-                        headers.append("Title", "You need to lock in!".parse().unwrap());
-                        headers.append("Tags", "warning".parse().unwrap());
-                        format!(
-                            "You worked for {} less than your goal was.",
-                            sec_to_hms(sec_over_goal)
-                        )
+                match deadline_alerts_from_row(&i) {
+                    Ok(alerts) => {
+                        if let Some(left) = alerts
+                            .into_iter()
+                            .find(|a| (WEEK_START - a).rem_euclid(WEEK) == time_passed)
+                        {
+                            deadline_alert(&username, monday, time_passed, left).await;
+                        }
                     }
-                } else {
-                    if sec_over_goal >= 0 {
-                        headers.append(
-                            "Title",
-                            "Good Job, don't forget to submit!".parse().unwrap(),
-                        );
-                        headers.append("Tags", "tada".parse().unwrap());
-                        format!(
-                            "You worked for {} more than your weekly goal was.",
-                            sec_to_hms(sec_over_goal)
-                        )
-                    } else {
-                        headers.append("Title", "You need to lock in!".parse().unwrap());
-                        headers.append("Tags", "warning".parse().unwrap());
-                        format!(
-                            "You're {} short of your weekly goal and you need to submit soon.",
-                            sec_to_hms(sec_over_goal)
-                        )
+                    Err(e) => eprintln!("{}", e),
+                }
+                let (settings, day) = match due_reminder(&username, settings, monday, time_passed).await {
+                    Ok(Some(d)) => d,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        continue;
                     }
                 };
-
-                let client = Client::new();
-                client
-                    .post(format!("https://ntfy.tim.hackclub.app/{}", username))
-                    .headers(headers)
-                    .body(msg)
-                    .send()
-                    .await
-                    .unwrap();
+                match evaluate(&username, &i, settings, day, monday, time_passed).await {
+                    Ok(reminder) => {
+                        let sent = deliver(&username, reminder, true).await;
+                        log_event("reminder", &username, sent, "").await;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        log_event("reminder", &username, false, &e).await;
+                    }
+                }
+            }
+            *LAST_RUN.lock().unwrap() = Some((
+                monday + chrono::Duration::minutes(time_passed),
+                Utc::now().naive_utc(),
+            ));
+            if time_passed % 60 == 0 {
+                spawn(refresh_calendars());
+                prune_events().await;
+            }
+            if time_passed % 60 == 30 {
+                spawn(record_snapshots());
+            }
+            if time_passed == WEEK_START {
+                missed_submissions(monday).await;
             }
             if time_passed == 0 {
-                match query("UPDATE users SET project = ''").execute(&*POOL).await {
+                start_week(monday).await;
+                match query("UPDATE users SET balance = CASE WHEN carry_mode = 2 THEN bank ELSE 0 END").execute(&*POOL).await {
                     Ok(_) => (),
                     Err(e) => {
                         eprintln!("{}", e);
@@ -182,13 +140,704 @@ pub async fn notifications() {
     }
 }
 
-fn get_project(data: Value, project: String) -> Option<i64> {
-    for i in data.get("data")?.get("projects")?.as_array()? {
-        if i.get("name")?.as_str()?.to_string() == project {
-            return i.get("total_seconds")?.as_i64();
+/// Deletes events the admin page doesn't show anymore, it only looks at the last two weeks.
+async fn prune_events() {
+    if let Err(e) = query("DELETE FROM events WHERE time < ?")
+        .bind((Utc::now() - chrono::Duration::days(28)).naive_utc().to_string())
+        .execute(&*POOL)
+        .await
+    {
+        eprintln!("{}", e);
+    }
+}
+
+/// A reminder worked out by `evaluate`, `deliver` records and sends it.
+pub struct Reminder {
+    pub day: usize,
+    /// When it's for.
+    pub at: NaiveDateTime,
+    /// Title, tags and message of every notification, in the order they go out.
+    pub notifications: Vec<(String, Option<String>, String)>,
+    /// Window start, target and seconds for `evaluations`, `None` without a tracked project.
+    pub result: Option<(NaiveDateTime, i64, i64)>,
+    /// Balance and bank after this reminder, with carry on.
+    pub carry: Option<(i64, i64)>,
+}
+
+/// Works out the reminder of `day` at `time_passed` without sending or storing anything.
+pub async fn evaluate(
+    username: &str,
+    row: &SqliteRow,
+    settings: Settings,
+    day: usize,
+    monday: NaiveDateTime,
+    time_passed: i64,
+) -> Result<Reminder, String> {
+    let now = monday + chrono::Duration::minutes(time_passed);
+    let mut reminder = Reminder {
+        day,
+        at: now,
+        notifications: Vec::new(),
+        result: None,
+        carry: None,
+    };
+    let projects = tracked_projects(username).await.map_err(|e| e.to_string())?;
+    if projects.is_empty() {
+        reminder.notifications.push((
+            "Set your project.".to_string(),
+            None,
+            "You haven't set a project for this week.".to_string(),
+        ));
+        return Ok(reminder);
+    }
+    let goal = settings.goal(day);
+    let week_window = settings.slots()[day].window == Window::WeekToDate;
+    let sunday = day == 6;
+    let Carry { mode: carry_mode, cap: bank_cap, balance, bank } =
+        carry_from_row(row).map_err(|e| e.to_string())?;
+
+    let start = monday
+        .checked_add_signed(chrono::Duration::minutes(settings.window_start(day)))
+        .unwrap()
+        .and_utc();
+    let data = stats(username, "projects", start, None).await?;
+
+    let aliases = aliases(username).await.map_err(|e| e.to_string())?;
+    let breakdown = get_projects(&data, &projects, &aliases);
+    let seconds_spend: i64 = breakdown.iter().map(|p| p.1).sum();
+
+    let empty: Vec<&str> = breakdown
+        .iter()
+        .filter(|p| p.1 == 0)
+        .map(|p| p.0.as_str())
+        .collect();
+    let others = untracked_projects(&data, &projects, &aliases);
+    if !empty.is_empty() && !others.is_empty() {
+        reminder.notifications.push((
+            "No time on your project.".to_string(),
+            Some("mag".to_string()),
+            format!(
+                "{} got no time, but {} did. If your project was renamed add an alias.",
+                empty.join(", "),
+                others
+                    .iter()
+                    .map(|(name, sec)| format!("{} ({})", name, hms(*sec)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ));
+    }
+
+    let target = match (carry_mode, week_window) {
+        (CarryMode::Off, _) => goal * 60 * 60,
+        (_, false) => adjusted_goal(
+            goal * 60 * 60,
+            balance,
+            day,
+            &settings.slots().map(|s| s.goal),
+        ),
+        (_, true) => adjusted_weekly_goal(goal * 60 * 60, bank),
+    };
+    let sec_over_goal = seconds_spend - target;
+    reminder.result = Some((start.naive_utc(), target, seconds_spend));
+
+    let (title, tags, mut msg) = if !sunday {
+        if sec_over_goal >= 0 {
+            ("Good Job!", "tada", format!(
+                "You worked for {} more than your goal was.",
+                sec_to_hms(sec_over_goal)
+            ))
+        } else {
+            ("You need to lock in!", "warning", format!(
+                "You worked for {} less than your goal was.",
+                sec_to_hms(sec_over_goal)
+            ))
+        }
+    } else {
+        if sec_over_goal >= 0 {
+            ("Good Job, don't forget to submit!", "tada", format!(
+                "You worked for {} more than your weekly goal was.",
+                sec_to_hms(sec_over_goal)
+            ))
+        } else {
+            ("You need to lock in!", "warning", format!(
+                "You're {} short of your weekly goal and you need to submit soon.",
+                sec_to_hms(sec_over_goal)
+            ))
+        }
+    };
+
+    if sunday {
+        match submission(username, week_of(now)).await {
+            Ok(Some(s)) if s.url.is_empty() => msg.push_str("\nYou shipped this week."),
+            Ok(Some(s)) => msg.push_str(&format!("\nYou shipped this week: {}", s.url)),
+            Ok(None) => msg.push_str("\nYou haven't shipped this week yet."),
+            Err(e) => eprintln!("{}", e),
+        }
+        if let Some(left) = checklist_left(username, &projects).await {
+            msg.push('\n');
+            msg.push_str(&left);
+        }
+    }
+
+    if breakdown.len() > 1 {
+        msg.push('\n');
+        msg.push_str(
+            &breakdown
+                .iter()
+                .map(|(name, sec)| format!("{}: {}", name, hms(*sec)))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+
+    let week_seconds = if week_window {
+        Some(seconds_spend)
+    } else if settings.projection(time_passed, 0).is_some() {
+        let week_start = if time_passed >= WEEK_START { WEEK_START } else { WEEK_START - WEEK };
+        match stats(username, "projects", (monday + chrono::Duration::minutes(week_start)).and_utc(), None).await {
+            Ok(d) => Some(get_projects(&d, &projects, &aliases).iter().map(|p| p.1).sum()),
+            Err(_) => None,
+        }
+    } else {
+        None
+    };
+    if let Some(pace) = week_seconds.and_then(|s| settings.projection(time_passed, s)) {
+        msg.push('\n');
+        msg.push_str(&pace.message());
+    }
+
+    match long_goal_status(username, row, &aliases, settings.local_date(now)).await {
+        Ok(Some(status)) if status.behind > 0 => msg.push_str(&format!(
+            "\nYou're {} behind your goal of {}h on {}, that's {} a week until {}.",
+            hms(status.behind),
+            status.goal.hours,
+            status.goal.project,
+            hms(status.required),
+            status.goal.end.format("%d.%m.")
+        )),
+        Ok(_) => (),
+        Err(e) => eprintln!("{}", e),
+    }
+
+    if carry_mode != CarryMode::Off {
+        let balance = if week_window {
+            balance
+        } else {
+            balance + seconds_spend - goal * 60 * 60
+        };
+        let (balance, bank) = if !sunday {
+            (balance, bank)
+        } else {
+            let bank = settle_week(
+                carry_mode,
+                if week_window { sec_over_goal } else { balance },
+                bank_cap,
+            );
+            (bank, bank)
+        };
+        msg.push_str(&format!(
+            "\nAdjusted target: {}, balance: {}",
+            hms(target),
+            signed_hms(balance)
+        ));
+        reminder.carry = Some((balance, bank));
+    }
+
+    reminder.notifications.push((title.to_string(), Some(tags.to_string()), msg));
+    Ok(reminder)
+}
+
+/// Stores the result of a reminder from `evaluate` and sends its notifications, `false` if one
+/// of them couldn't be sent. Resends pass `record` false, they only notify so the evaluation and
+/// carry aren't counted twice.
+pub async fn deliver(username: &str, reminder: Reminder, record: bool) -> bool {
+    if let Some((window_start, target, seconds)) = reminder.result.filter(|_| record) {
+        if let Err(e) = query("INSERT INTO evaluations (username, time, day, window_start, target, seconds) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(username)
+            .bind(reminder.at.to_string())
+            .bind(reminder.day as i64)
+            .bind(window_start.to_string())
+            .bind(target)
+            .bind(seconds)
+            .execute(&*POOL)
+            .await
+        {
+            eprintln!("{}", e);
+        }
+    }
+    if let Some((balance, bank)) = reminder.carry.filter(|_| record) {
+        if let Err(e) = query("UPDATE users SET balance = ?, bank = ? WHERE username = ?")
+            .bind(balance)
+            .bind(bank)
+            .bind(username)
+            .execute(&*POOL)
+            .await
+        {
+            eprintln!("{}", e);
+        }
+    }
+    let mut sent = true;
+    for (title, tags, msg) in reminder.notifications {
+        sent &= send(username, &title, tags.as_deref(), &msg).await;
+    }
+    sent
+}
+
+/// The reminder that goes out now and the day it belongs to. Reminders during a busy event wait
+/// until it's over, vacations and holidays skip them.
+async fn due_reminder(
+    username: &str,
+    settings: Settings,
+    monday: NaiveDateTime,
+    time_passed: i64,
+) -> Result<Option<(Settings, usize)>, sqlx::Error> {
+    let at = |minute: i64| monday + chrono::Duration::minutes(minute);
+    let today = settings.local_date(at(time_passed));
+    // every day a reminder can still be pushed into
+    let overrides = overrides_between(username, today - Days::new(3), today).await?;
+
+    // (scheduled minute, day, override goal)
+    let mut scheduled: Vec<(i64, usize, Option<i32>)> = settings
+        .slots()
+        .iter()
+        .enumerate()
+        .map(|(day, s)| (s.time.rem_euclid(WEEK), day, None))
+        .collect();
+    for o in overrides.iter().filter(|o| o.date >= today - Days::new(1)) {
+        let time = o.date.and_hms_opt(0, 0, 0).unwrap()
+            - chrono::Duration::seconds(settings.offset as i64)
+            + chrono::Duration::minutes(o.time as i64);
+        let day = o.date.weekday().num_days_from_monday() as usize;
+        scheduled.push(((time - monday).num_minutes(), day, Some(o.goal)));
+    }
+    scheduled.retain(|s| (0.max(time_passed - PUSH_MINUTES)..=time_passed).contains(&s.0));
+    let first = match scheduled.iter().map(|s| s.0).min() {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+
+    // a day either side for the holidays of the local dates
+    let occurrences = calendar_occurrences(
+        username,
+        at(first) - chrono::Duration::days(1),
+        at(time_passed) + chrono::Duration::days(1),
+    )
+    .await?;
+    let fires = |minute: i64| {
+        let free = free_at(&occurrences, at(minute));
+        // pushed reminders still go out before the week resets
+        ((free - monday).num_seconds() + 59).div_euclid(60).min(WEEK - 1)
+    };
+
+    for (minute, day, goal) in scheduled {
+        if fires(minute) != time_passed {
+            continue;
+        }
+        let date = settings.local_date(at(minute));
+        if on_vacation(username, date).await? || holiday_on(&occurrences, settings.offset, date) {
+            continue;
+        }
+        match goal {
+            Some(goal) => return Ok(Some((settings.with_slot(day, minute, goal), day))),
+            None if !overrides.iter().any(|o| o.date == date) => {
+                return Ok(Some((settings, day)))
+            }
+            None => (),
+        }
+    }
+    Ok(None)
+}
+
+/// Counts down to the submission deadline with the time worked this week, until the user ships.
+async fn deadline_alert(username: &str, monday: NaiveDateTime, time_passed: i64, left: i64) {
+    let now = monday + chrono::Duration::minutes(time_passed);
+    match shipped(username, week_of(now)).await {
+        Ok(false) => (),
+        Ok(true) => return,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    }
+    // projects are reset monday 00:00, the last hours before the deadline use the weeks projects
+    let projects = if time_passed < WEEK_START {
+        week_projects(username, week_of(now)).await
+    } else {
+        tracked_projects(username).await
+    };
+    let (projects, aliases) = match (projects, aliases(username).await) {
+        (Ok(p), Ok(a)) => (p, a),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let week_start = if time_passed >= WEEK_START { WEEK_START } else { WEEK_START - WEEK };
+    let mut msg = format!("You have to submit in {}.", hms(left * 60));
+    if let Ok(d) = stats(username, "projects", (monday + chrono::Duration::minutes(week_start)).and_utc(), None).await {
+        let seconds: i64 = get_projects(&d, &projects, &aliases).iter().map(|p| p.1).sum();
+        msg.push_str(&format!(" You worked {} this week.", hms(seconds)));
+    }
+    if let Some(left) = checklist_left(username, &projects).await {
+        msg.push('\n');
+        msg.push_str(&left);
+    }
+    let ship = match action_token(username).await {
+        Ok(token) => action("Shipped!", &format!("/actions/ship/{}", token)),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    };
+    let actions = [submit_link(), ship].into_iter().flatten().collect::<Vec<_>>().join("; ");
+    send_with_actions(
+        username,
+        "Submission deadline soon.",
+        Some("hourglass_flowing_sand"),
+        &msg,
+        (!actions.is_empty()).then_some(actions.as_str()),
+    )
+    .await;
+}
+
+/// Like "2 checklist items left: demo link, devlog", `None` once everything is done.
+async fn checklist_left(username: &str, projects: &[String]) -> Option<String> {
+    let items = match checklist(username, projects).await {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            return None;
+        }
+    };
+    let left: Vec<String> = items
+        .into_iter()
+        .filter(|i| !i.done)
+        .map(|i| match projects.len() {
+            1 => i.name,
+            _ => format!("{} ({})", i.name, i.project),
+        })
+        .collect();
+    match left.len() {
+        0 => None,
+        1 => Some(format!("1 checklist item left: {}", left[0])),
+        n => Some(format!("{} checklist items left: {}", n, left.join(", "))),
+    }
+}
+
+async fn long_goal_status(
+    username: &str,
+    row: &SqliteRow,
+    aliases: &[Alias],
+    today: NaiveDate,
+) -> Result<Option<GoalStatus>, sqlx::Error> {
+    let goal = match long_goal_from_row(row)? {
+        Some(g) if g.start <= today && today <= g.end => g,
+        _ => return Ok(None),
+    };
+    let daily = daily_totals(&snapshots(username).await?, &goal.project, aliases);
+    Ok(Some(goal.status(&daily, today)))
+}
+
+/// Reminds about milestones that are due in one of their offsets days.
+async fn milestone_reminders(username: &str, today: NaiveDate) {
+    let milestones = match milestones(username).await {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    for m in milestones.into_iter().filter(|m| !m.done) {
+        let days = (m.due - today).num_days();
+        if !m.offsets.contains(&(days as i32)) {
+            continue;
+        }
+        let token: String = match query("SELECT token FROM milestones WHERE id = ?")
+            .bind(m.id)
+            .fetch_one(&*POOL)
+            .await
+        {
+            Ok(r) => r.get("token"),
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let mut msg = match days {
+            0 => format!("{} on {} is due today.", m.name, m.project),
+            1 => format!("{} on {} is due tomorrow.", m.name, m.project),
+            d => format!("{} on {} is due in {} days.", m.name, m.project, d),
+        };
+        if let Some(target) = m.target {
+            msg.push_str(&format!(" {} of {}h done.", hms(m.progress), target));
+        }
+        send_with_actions(
+            username,
+            "Milestone due soon.",
+            Some("triangular_flag_on_post"),
+            &msg,
+            action("Mark done", &format!("/actions/milestone/{}/done", token)).as_deref(),
+        )
+        .await;
+    }
+}
+
+/// Fetches calendars imported from a URL again and stores the upcoming occurrences of every
+/// calendar.
+async fn refresh_calendars() {
+    let rows = match query(
+        "SELECT c.id, c.username, c.url, c.content, u.utc_offset FROM calendars c JOIN users u ON u.username = c.username",
+    )
+    .fetch_all(&*POOL)
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    for i in rows {
+        let id: i64 = i.get("id");
+        let url: String = i.get("url");
+        let mut content: String = i.get("content");
+        if !url.is_empty() {
+            match fetch(&url).await {
+                Ok(c) => content = c,
+                // keep the last working version
+                Err(e) => eprintln!("{}: {}", url, e),
+            }
+            if let Err(e) = query("UPDATE calendars SET content = ? WHERE id = ?")
+                .bind(content.clone())
+                .bind(id)
+                .execute(&*POOL)
+                .await
+            {
+                eprintln!("{}", e);
+            }
+        }
+        if let Err(e) =
+            save_occurrences(id, i.get("username"), &content, i.get("utc_offset")).await
+        {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+/// Tells everyone who worked on something last week but didn't ship it, once the deadline passed.
+async fn missed_submissions(monday: NaiveDateTime) {
+    let week = monday.date() - Days::new(7);
+    let rows = match query("SELECT username FROM users WHERE disabled = 0").fetch_all(&*POOL).await {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    for i in rows {
+        let username: String = i.get("username");
+        match (week_projects(&username, week).await, shipped(&username, week).await) {
+            (Ok(projects), Ok(false)) if !projects.is_empty() => {
+                send(
+                    &username,
+                    "You didn't ship last week.",
+                    Some("package"),
+                    &format!("There's no submission for {} last week.", projects.join(", ")),
+                )
+                .await;
+            }
+            (Err(e), _) | (_, Err(e)) => eprintln!("{}", e),
+            _ => (),
+        }
+    }
+}
+
+/// Applies everyone's project carry over choice and tells them what happened.
+async fn start_week(monday: NaiveDateTime) {
+    let rows = match query("SELECT username, project_carry, project_carry_days FROM users WHERE disabled = 0")
+        .fetch_all(&*POOL)
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
         }
+    };
+    for i in rows {
+        let username: String = i.get("username");
+        let carry = match project_carry_from_row(&i) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let kept = match tracked_projects(&username).await {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+
+        let (projects, msg) = match carry {
+            ProjectCarry::Keep if kept.is_empty() => (
+                Vec::new(),
+                "You aren't tracking a project, set your project for this week.".to_string(),
+            ),
+            ProjectCarry::Keep => {
+                let msg = format!("You're still tracking {}.", kept.join(", "));
+                (kept, msg)
+            }
+            ProjectCarry::MostWorked(days) => {
+                let start = Utc::now() - chrono::Duration::days(days as i64);
+                match stats(&username, "projects", start, None).await {
+                    Ok(d) => match all_projects(&d).into_iter().max_by_key(|p| p.1) {
+                        Some((name, sec)) if sec > 0 => {
+                            let msg = format!(
+                                "You're now tracking {}, your most worked on project of the last {} days.",
+                                name, days
+                            );
+                            (vec![name], msg)
+                        }
+                        _ => (
+                            Vec::new(),
+                            "You didn't work on anything recently, set your project for this week.".to_string(),
+                        ),
+                    },
+                    // Hackatime being down isn't a reason to lose the projects
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        let msg = if kept.is_empty() {
+                            "Hackatime couldn't be reached to pick your project, set your project for this week.".to_string()
+                        } else {
+                            format!(
+                                "Hackatime couldn't be reached to pick your project, you're still tracking {}.",
+                                kept.join(", ")
+                            )
+                        };
+                        (kept, msg)
+                    }
+                }
+            }
+            ProjectCarry::Clear => (
+                Vec::new(),
+                "Your projects were cleared, set your project for this week.".to_string(),
+            ),
+        };
+
+        let mut tx = match POOL.begin().await {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        if let Err(e) = query("DELETE FROM projects WHERE username = ?")
+            .bind(username.clone())
+            .execute(&mut *tx)
+            .await
+        {
+            eprintln!("{}", e);
+            continue;
+        }
+        for project in &projects {
+            if let Err(e) = query("INSERT INTO projects (username, name) VALUES (?, ?)")
+                .bind(username.clone())
+                .bind(project)
+                .execute(&mut *tx)
+                .await
+            {
+                eprintln!("{}", e);
+            }
+        }
+        if let Err(e) = tx.commit().await {
+            eprintln!("{}", e);
+            continue;
+        }
+        // the old week still runs until the deadline, these are the projects of the next one
+        if let Err(e) = save_week_projects(&username, monday.date(), &projects).await {
+            eprintln!("{}", e);
+        }
+        if let Err(e) = create_checklists(&username, &projects).await {
+            eprintln!("{}", e);
+        }
+
+        send(&username, "A new week started.", Some("calendar"), &msg).await;
     }
-    None
+}
+
+/// Posts a push notification to the users ntfy topic.
+pub(crate) async fn send(username: &str, title: &str, tags: Option<&str>, body: &str) -> bool {
+    send_with_actions(username, title, tags, body, None).await
+}
+
+/// Like `send`, with ntfy action buttons.
+async fn send_with_actions(
+    username: &str,
+    title: &str,
+    tags: Option<&str>,
+    body: &str,
+    actions: Option<&str>,
+) -> bool {
+    let mut headers = HeaderMap::new();
+    if let Ok(title) = title.parse() {
+        headers.append("Title", title);
+    }
+    if let Some(tags) = tags {
+        headers.append("Tags", tags.parse().unwrap());
+    }
+    if let Some(Ok(actions)) = actions.map(|a| a.parse()) {
+        headers.append("Actions", actions);
+    }
+    let topic = match ntfy_topic(username).await {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    post(username, &topic, title, headers, body).await
+}
+
+/// Like `send` but to `topic`, for the login code of an account that doesn't exist yet.
+pub(crate) async fn send_to(
+    username: &str,
+    topic: &str,
+    title: &str,
+    tags: Option<&str>,
+    body: &str,
+) -> bool {
+    let mut headers = HeaderMap::new();
+    if let Ok(title) = title.parse() {
+        headers.append("Title", title);
+    }
+    if let Some(tags) = tags {
+        headers.append("Tags", tags.parse().unwrap());
+    }
+    post(username, topic, title, headers, body).await
+}
+
+async fn post(username: &str, topic: &str, title: &str, headers: HeaderMap, body: &str) -> bool {
+    let client = Client::new();
+    let error = match client
+        .post(format!("https://ntfy.tim.hackclub.app/{}", topic))
+        .headers(headers)
+        .body(body.to_string())
+        .send()
+        .await
+    {
+        Ok(r) if r.status().is_success() => None,
+        Ok(r) => Some(r.status().to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(e) = &error {
+        eprintln!("{}", e);
+    }
+    log_event("ntfy", username, error.is_none(), error.as_deref().unwrap_or(title)).await;
+    error.is_none()
 }
 
 fn sec_to_hms(sec: i64) -> String {
@@ -207,3 +856,18 @@ fn sec_to_hms(sec: i64) -> String {
     }
     out
 }
+
+fn hms(sec: i64) -> String {
+    match sec {
+        0 => "0s".to_string(),
+        s => sec_to_hms(s).trim_end().to_string(),
+    }
+}
+
+fn signed_hms(sec: i64) -> String {
+    match sec {
+        0 => hms(sec),
+        s if s < 0 => format!("-{}", hms(s)),
+        s => format!("+{}", hms(s)),
+    }
+}
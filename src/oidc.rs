@@ -0,0 +1,638 @@
+#![cfg(feature = "ssr")]
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, NaiveDateTime, Utc};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{query, Row};
+use std::{collections::HashMap, env};
+
+use crate::{
+    auth::{cookie, cookie_from, new_session, user_from},
+    db::{new_token, POOL},
+};
+
+/// Minutes a login may take at the provider, and to confirm linking it.
+const STATE_MINUTES: i64 = 10;
+/// Holds the state of the login this browser started.
+const STATE_COOKIE: &str = "oidc_state";
+/// Holds the token of a login waiting to be linked to the logged in user.
+pub const LINK_COOKIE: &str = "oidc_link";
+
+/// The provider from `OIDC_ISSUER`, `OIDC_CLIENT_ID` and `OIDC_CLIENT_SECRET`.
+struct Config {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+}
+
+fn config() -> Option<Config> {
+    let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+    Some(Config {
+        issuer: var("OIDC_ISSUER")?.trim_end_matches('/').to_string(),
+        client_id: var("OIDC_CLIENT_ID")?,
+        client_secret: var("OIDC_CLIENT_SECRET")?,
+    })
+}
+
+/// Whether logging in with the provider is set up.
+pub fn enabled() -> bool {
+    config().is_some() && env::var("BASE_URL").is_ok()
+}
+
+fn redirect_uri() -> Option<String> {
+    let base = env::var("BASE_URL").ok()?;
+    Some(format!("{}/auth/oidc/callback", base.trim_end_matches('/')))
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+/// The providers endpoints from its `.well-known/openid-configuration`.
+async fn discover(issuer: &str) -> Result<Discovery, String> {
+    let discovery: Discovery = reqwest::get(format!("{}/.well-known/openid-configuration", issuer))
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    if discovery.issuer.trim_end_matches('/') != issuer {
+        return Err(format!("Discovery is for {}", discovery.issuer));
+    }
+    Ok(discovery)
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Trades the code from the callback for an ID token.
+async fn exchange(
+    config: &Config,
+    token_endpoint: &str,
+    code: &str,
+    redirect_uri: &str,
+) -> Result<String, String> {
+    let response: TokenResponse = reqwest::Client::new()
+        .post(token_endpoint)
+        .basic_auth(&config.client_id, Some(&config.client_secret))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+        ])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response.id_token)
+}
+
+/// Who logged in at the provider.
+#[derive(Debug, PartialEq)]
+struct Identity {
+    subject: String,
+    /// Only set if the provider says it's verified.
+    email: Option<String>,
+    /// From the claim named by `OIDC_USERNAME_CLAIM`, only to tell who to log in as first.
+    username: Option<String>,
+}
+
+/// Checks the claims of an ID token. The signature isn't checked, the token comes straight from
+/// the providers token endpoint.
+fn identity(
+    id_token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+    username_claim: &str,
+    now: i64,
+) -> Result<Identity, String> {
+    let payload = id_token.split('.').nth(1).ok_or("Malformed ID token")?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|e| e.to_string())?;
+    let claims: Value = serde_json::from_slice(&payload).map_err(|e| e.to_string())?;
+    let text = |name: &str| claims.get(name).and_then(|c| c.as_str());
+
+    if text("iss").map(|i| i.trim_end_matches('/')) != Some(issuer) {
+        return Err("ID token from another issuer".to_string());
+    }
+    let audience = match claims.get("aud") {
+        Some(Value::String(a)) => a == client_id,
+        Some(Value::Array(a)) => a.iter().any(|a| a.as_str() == Some(client_id)),
+        _ => false,
+    };
+    if !audience {
+        return Err("ID token for another client".to_string());
+    }
+    if claims
+        .get("exp")
+        .and_then(|e| e.as_i64())
+        .is_none_or(|e| e <= now)
+    {
+        return Err("ID token expired".to_string());
+    }
+    if text("nonce") != Some(nonce) {
+        return Err("ID token for another login".to_string());
+    }
+    let subject = match text("sub") {
+        Some(s) if !s.is_empty() => s.to_string(),
+        _ => return Err("ID token without subject".to_string()),
+    };
+    let verified = claims.get("email_verified").and_then(|v| v.as_bool()) == Some(true);
+    Ok(Identity {
+        subject,
+        email: text("email").filter(|_| verified).map(|e| e.to_lowercase()),
+        username: text(username_claim)
+            .filter(|u| !u.is_empty())
+            .map(|u| u.to_string()),
+    })
+}
+
+/// Serves `/auth/oidc/login`, sends the browser to the provider.
+pub async fn login() -> Response {
+    let (config, redirect_uri) = match (config(), redirect_uri()) {
+        (Some(c), Some(r)) => (c, r),
+        _ => return (StatusCode::NOT_FOUND, "Single sign-on isn't set up").into_response(),
+    };
+    let discovery = match discover(&config.issuer).await {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::BAD_GATEWAY, "Couldn't reach the provider").into_response();
+        }
+    };
+    let state = new_token();
+    let nonce = new_token();
+    let now = Utc::now().naive_utc();
+    if let Err(e) = query("DELETE FROM oidc_states WHERE created < ?")
+        .bind((now - Duration::minutes(STATE_MINUTES)).to_string())
+        .execute(&*POOL)
+        .await
+    {
+        eprintln!("{}", e);
+    }
+    if let Err(e) = query("INSERT INTO oidc_states (state, nonce, created) VALUES (?, ?, ?)")
+        .bind(state.clone())
+        .bind(nonce.clone())
+        .bind(now.to_string())
+        .execute(&*POOL)
+        .await
+    {
+        eprintln!("{}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+    match Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        [
+            ("response_type", "code"),
+            ("scope", "openid email profile"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("state", state.as_str()),
+            ("nonce", nonce.as_str()),
+        ],
+    ) {
+        Ok(url) => (
+            [(
+                header::SET_COOKIE,
+                cookie(STATE_COOKIE, &state, STATE_MINUTES * 60),
+            )],
+            Redirect::to(url.as_str()),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                "The provider has a broken configuration",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Serves `/auth/oidc/callback`, where the provider sends the browser back to.
+pub async fn callback(
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    let (config, redirect_uri) = match (config(), redirect_uri()) {
+        (Some(c), Some(r)) => (c, r),
+        _ => return (StatusCode::NOT_FOUND, "Single sign-on isn't set up").into_response(),
+    };
+    if let Some(e) = params.get("error") {
+        return (StatusCode::BAD_REQUEST, format!("The provider says: {}", e)).into_response();
+    }
+    let (code, state) = match (params.get("code"), params.get("state")) {
+        (Some(c), Some(s)) => (c, s),
+        _ => return (StatusCode::BAD_REQUEST, "Missing code").into_response(),
+    };
+
+    // the state cookie proves this browser started the login, so nobody can send someone else
+    // through the callback with their own login
+    if cookie_from(&headers, STATE_COOKIE) != Some(state.as_str()) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "This login was started in another browser",
+        )
+            .into_response();
+    }
+    let nonce = match query("SELECT * FROM oidc_states WHERE state = ?")
+        .bind(state)
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => {
+            let created = NaiveDateTime::parse_from_str(
+                &r.get::<String, _>("created"),
+                "%Y-%m-%d %H:%M:%S%.f",
+            );
+            match created {
+                Ok(c) if Utc::now().naive_utc() - c < Duration::minutes(STATE_MINUTES) => {
+                    r.get::<String, _>("nonce")
+                }
+                _ => return (StatusCode::BAD_REQUEST, "Login took too long").into_response(),
+            }
+        }
+        Ok(None) => return (StatusCode::BAD_REQUEST, "Unknown login").into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    if let Err(e) = query("DELETE FROM oidc_states WHERE state = ?")
+        .bind(state)
+        .execute(&*POOL)
+        .await
+    {
+        eprintln!("{}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+
+    let id_token = match discover(&config.issuer).await {
+        Ok(d) => exchange(&config, &d.token_endpoint, code, &redirect_uri).await,
+        Err(e) => Err(e),
+    };
+    let username_claim =
+        env::var("OIDC_USERNAME_CLAIM").unwrap_or("preferred_username".to_string());
+    let identity = match id_token.and_then(|t| {
+        identity(
+            &t,
+            &config.issuer,
+            &config.client_id,
+            &nonce,
+            &username_claim,
+            Utc::now().timestamp(),
+        )
+    }) {
+        Ok(i) => i,
+        Err(e) => {
+            eprintln!("{}", e);
+            return (
+                StatusCode::BAD_GATEWAY,
+                "The provider didn't confirm the login",
+            )
+                .into_response();
+        }
+    };
+
+    let clear_state = cookie(STATE_COOKIE, "", 0);
+    let username = match linked_user(&config.issuer, &identity).await {
+        Ok(Some(u)) => u,
+        Ok(None) => return unlinked(&config.issuer, &identity, &headers, clear_state).await,
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    match new_session(&username).await {
+        Ok(session) => (
+            AppendHeaders([
+                (header::SET_COOKIE, clear_state),
+                (header::SET_COOKIE, session),
+            ]),
+            Redirect::to("/"),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// A login nobody linked yet. A logged in user gets asked on the home page whether to link it,
+/// everyone else has to log in with a code first.
+async fn unlinked(
+    issuer: &str,
+    identity: &Identity,
+    headers: &HeaderMap,
+    clear_state: String,
+) -> Response {
+    let username = match user_from(headers).await {
+        Ok(Some(u)) => u,
+        Ok(None) => {
+            let who = match &identity.username {
+                Some(u) => format!(" as {}", u),
+                None => "".to_string(),
+            };
+            return (
+                StatusCode::FORBIDDEN,
+                format!(
+                    "No account for this login yet, log in{} with a code and use single sign-on again to link it",
+                    who
+                ),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    let token = new_token();
+    let now = Utc::now().naive_utc();
+    let saved = async {
+        query("DELETE FROM oidc_links WHERE created < ?")
+            .bind((now - Duration::minutes(STATE_MINUTES)).to_string())
+            .execute(&*POOL)
+            .await?;
+        query("INSERT INTO oidc_links (token, username, issuer, subject, email, created) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(token.clone())
+            .bind(username)
+            .bind(issuer)
+            .bind(identity.subject.clone())
+            .bind(identity.email.clone().unwrap_or_default())
+            .bind(now.to_string())
+            .execute(&*POOL)
+            .await
+    };
+    match saved.await {
+        Ok(_) => (
+            AppendHeaders([
+                (header::SET_COOKIE, clear_state),
+                (
+                    header::SET_COOKIE,
+                    cookie(LINK_COOKIE, &token, STATE_MINUTES * 60),
+                ),
+            ]),
+            Redirect::to("/"),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// The user an identity belongs to, the one it was linked to or else the one with the same
+/// verified email.
+async fn linked_user(issuer: &str, identity: &Identity) -> Result<Option<String>, sqlx::Error> {
+    let known = query("SELECT username FROM identities WHERE issuer = ? AND subject = ?")
+        .bind(issuer)
+        .bind(identity.subject.clone())
+        .fetch_optional(&*POOL)
+        .await?
+        .map(|r| r.get::<String, _>("username"));
+    let by_email = match (&known, &identity.email) {
+        (None, Some(email)) => query("SELECT username FROM users WHERE email = ?")
+            .bind(email)
+            .fetch_optional(&*POOL)
+            .await?
+            .map(|r| r.get::<String, _>("username")),
+        _ => None,
+    };
+    let username = match known.or(by_email) {
+        Some(u) => u,
+        None => return Ok(None),
+    };
+    save_identity(
+        issuer,
+        &identity.subject,
+        identity.email.as_deref().unwrap_or_default(),
+        &username,
+    )
+    .await?;
+    Ok(Some(username))
+}
+
+async fn save_identity(
+    issuer: &str,
+    subject: &str,
+    email: &str,
+    username: &str,
+) -> Result<(), sqlx::Error> {
+    query(
+        "INSERT OR REPLACE INTO identities (issuer, subject, username, email) VALUES (?, ?, ?, ?)",
+    )
+    .bind(issuer)
+    .bind(subject)
+    .bind(username)
+    .bind(email)
+    .execute(&*POOL)
+    .await?;
+    if !email.is_empty() {
+        query("UPDATE users SET email = ? WHERE username = ?")
+            .bind(email)
+            .bind(username)
+            .execute(&*POOL)
+            .await?;
+    }
+    Ok(())
+}
+
+/// A login from the callback that waits for the user to confirm linking it.
+pub struct PendingLink {
+    pub token: String,
+    pub issuer: String,
+    pub subject: String,
+    pub email: String,
+}
+
+/// The login in the `oidc_link` cookie of `headers` waiting to be linked to `username`.
+pub async fn pending_link(
+    headers: &HeaderMap,
+    username: &str,
+) -> Result<Option<PendingLink>, sqlx::Error> {
+    let token = match cookie_from(headers, LINK_COOKIE) {
+        Some(t) if !t.is_empty() => t,
+        _ => return Ok(None),
+    };
+    let oldest = Utc::now().naive_utc() - Duration::minutes(STATE_MINUTES);
+    Ok(
+        query("SELECT * FROM oidc_links WHERE token = ? AND username = ? AND created >= ?")
+            .bind(token)
+            .bind(username)
+            .bind(oldest.to_string())
+            .fetch_optional(&*POOL)
+            .await?
+            .map(|r| PendingLink {
+                token: r.get("token"),
+                issuer: r.get("issuer"),
+                subject: r.get("subject"),
+                email: r.get("email"),
+            }),
+    )
+}
+
+/// Links the waiting login to `username` if `confirm`, else forgets it. `false` if there's none.
+pub async fn finish_link(
+    headers: &HeaderMap,
+    username: &str,
+    confirm: bool,
+) -> Result<bool, sqlx::Error> {
+    let link = match pending_link(headers, username).await? {
+        Some(l) => l,
+        None => return Ok(false),
+    };
+    query("DELETE FROM oidc_links WHERE token = ?")
+        .bind(link.token)
+        .execute(&*POOL)
+        .await?;
+    if confirm {
+        save_identity(&link.issuer, &link.subject, &link.email, username).await?;
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::json;
+
+    fn token(claims: Value) -> String {
+        format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    fn claims() -> Value {
+        json!({
+            "iss": "http://issuer.test",
+            "aud": ["other", "siege"],
+            "sub": "u-1",
+            "exp": 2000,
+            "nonce": "n",
+            "email": "Orpheus@Hackclub.com",
+            "email_verified": true,
+            "preferred_username": "orpheus",
+        })
+    }
+
+    #[test]
+    fn checks_claims() {
+        let check = |claims: Value, now: i64| {
+            identity(
+                &token(claims),
+                "http://issuer.test",
+                "siege",
+                "n",
+                "preferred_username",
+                now,
+            )
+        };
+        assert_eq!(
+            check(claims(), 1000),
+            Ok(Identity {
+                subject: "u-1".to_string(),
+                email: Some("orpheus@hackclub.com".to_string()),
+                username: Some("orpheus".to_string()),
+            })
+        );
+        assert!(check(claims(), 2000).is_err());
+        for (claim, value) in [
+            ("iss", json!("http://evil.test")),
+            ("aud", json!("other")),
+            ("nonce", json!("m")),
+            ("sub", json!("")),
+        ] {
+            let mut c = claims();
+            c[claim] = value;
+            assert!(check(c, 1000).is_err(), "{}", claim);
+        }
+        // unverified emails can't be matched to accounts
+        for verified in [json!(false), json!("true"), Value::Null] {
+            let mut c = claims();
+            c["email_verified"] = verified;
+            assert_eq!(check(c, 1000).map(|i| i.email), Ok(None));
+        }
+    }
+
+    /// Discovery and the code exchange against a mock issuer on a local port.
+    #[tokio::test]
+    async fn mock_issuer() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let discovery = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+        });
+        let mut c = claims();
+        c["iss"] = json!(issuer);
+        let id_token = token(c);
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(move || async move { Json(discovery) }),
+            )
+            .route(
+                "/token",
+                post(move |headers: HeaderMap, body: String| async move {
+                    // client_secret_basic with siege:secret
+                    let basic = headers
+                        .get(header::AUTHORIZATION)
+                        .and_then(|h| h.to_str().ok());
+                    if basic != Some("Basic c2llZ2U6c2VjcmV0") || !body.contains("code=abc") {
+                        return Err(StatusCode::UNAUTHORIZED);
+                    }
+                    Ok(Json(json!({ "access_token": "x", "id_token": id_token })))
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let config = Config {
+            issuer: issuer.clone(),
+            client_id: "siege".to_string(),
+            client_secret: "secret".to_string(),
+        };
+        let d = discover(&issuer).await.unwrap();
+        assert_eq!(d.authorization_endpoint, format!("{}/authorize", issuer));
+        assert!(
+            exchange(&config, &d.token_endpoint, "wrong", "http://app.test/cb")
+                .await
+                .is_err()
+        );
+        let t = exchange(&config, &d.token_endpoint, "abc", "http://app.test/cb")
+            .await
+            .unwrap();
+        let i = identity(&t, &issuer, "siege", "n", "preferred_username", 1000).unwrap();
+        assert_eq!(i.subject, "u-1");
+    }
+}
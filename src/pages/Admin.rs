@@ -0,0 +1,234 @@
+use chrono::Utc;
+use leptos::{ev::SubmitEvent, prelude::*, task::spawn_local};
+
+use crate::{
+    datatypes::{AdminData, AdminUser, Role},
+    query_admin, query_admin_user,
+    schedule::WEEK,
+    set_user_disabled,
+};
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[component]
+pub fn AdminPage() -> impl IntoView {
+    let search = RwSignal::new("".to_string());
+    let inspected = RwSignal::new("".to_string());
+    let (error, set_error) = signal("".to_string());
+
+    let admin_loader = Resource::new(|| (), |_| query_admin());
+    let user_loader = Resource::new(
+        move || inspected.get(),
+        |username| async move {
+            match username.as_str() {
+                "" => Ok(None),
+                _ => query_admin_user(username).await.map(Some),
+            }
+        },
+    );
+
+    let inspect = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        inspected.set(search.get().trim().to_string());
+    };
+
+    let set_disabled = move |username: String, disabled: bool| {
+        spawn_local(async move {
+            match set_user_disabled(username, disabled).await {
+                Ok(_) => {
+                    set_error.set("".to_string());
+                    user_loader.refetch();
+                    admin_loader.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    view! {
+        <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+            {move || {
+                admin_loader
+                    .get()
+                    .map(|res| match res {
+                        Ok(data) => view! { <Overview data /> }.into_any(),
+                        Err(ServerFnError::ServerError(e)) => {
+                            view! { <p class="col-start-2 text-center pt-5 text-red-400">{e}</p> }.into_any()
+                        }
+                        Err(e) => {
+                            view! { <p class="col-start-2 text-center pt-5 text-red-400">{e.to_string()}</p> }.into_any()
+                        }
+                    })
+            }}
+        </Suspense>
+        <div class="col-start-3 row-start-1 justify-self-center pt-5 w-full pr-12">
+            <form class="grid grid-cols-[75%_25%]" on:submit=inspect>
+                <input class="col-start-1 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="username" type="text" placeholder="Username" bind:value=search />
+                <input class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Inspect" />
+            </form>
+            <Suspense fallback=move || view! { <p class="text-center">"Loading..."</p> }>
+                {move || {
+                    user_loader
+                        .get()
+                        .map(|res| match res {
+                            Ok(Some(user)) => view! { <Inspector user set_disabled /> }.into_any(),
+                            Ok(None) => ().into_any(),
+                            Err(ServerFnError::ServerError(e)) => view! { <p class="text-center text-red-400">{e}</p> }.into_any(),
+                            Err(e) => view! { <p class="text-center text-red-400">{e.to_string()}</p> }.into_any(),
+                        })
+                }}
+            </Suspense>
+            <p class="text-center text-red-400 pt-3">{error}</p>
+        </div>
+    }
+}
+
+/// Share of failures like "2.5% of 400 failed".
+fn failure_rate(outcomes: impl Iterator<Item = (i64, i64)>) -> String {
+    let (ok, failed) = outcomes.fold((0, 0), |a, o| (a.0 + o.0, a.1 + o.1));
+    match ok + failed {
+        0 => "none".to_string(),
+        total => format!("{:.1}% of {} failed", failed as f64 * 100.0 / total as f64, total),
+    }
+}
+
+#[component]
+fn Overview(data: AdminData) -> impl IntoView {
+    let week = data.days[data.days.len().saturating_sub(7)..].to_vec();
+    let scheduler = match data.scheduler {
+        Some((minute, done)) => format!(
+            "Went through {} UTC {}s late, that was {}s ago.",
+            minute.format("%H:%M"),
+            (done - minute).num_seconds(),
+            (Utc::now().naive_utc() - done).num_seconds(),
+        ),
+        None => "Hasn't run since the server started.".to_string(),
+    };
+
+    view! {
+        <div class="col-start-1 row-start-1 justify-self-center pt-5 w-full px-12">
+            <h2 class="text-center font-bold leading-12">Users</h2>
+            <p class="text-center">
+                {format!(
+                    "{} users, {} got a reminder in the last 7 days, {} disabled.",
+                    data.users,
+                    data.active,
+                    data.disabled,
+                )}
+            </p>
+            <h2 class="text-center font-bold leading-12 pt-3">Scheduler</h2>
+            <p class="text-center">{scheduler}</p>
+            <h2 class="text-center font-bold leading-12 pt-3">Last 7 days</h2>
+            <div class="grid grid-cols-[35%_65%]">
+                <p class="col-start-1">Reminders:</p>
+                <p class="col-start-2">{failure_rate(week.iter().map(|d| d.reminders))}</p>
+                <p class="col-start-1">ntfy:</p>
+                <p class="col-start-2">{failure_rate(week.iter().map(|d| d.ntfy))}</p>
+                <p class="col-start-1">Hackatime:</p>
+                <p class="col-start-2">{failure_rate(week.iter().map(|d| d.hackatime))}</p>
+            </div>
+            <a class="underline block text-center pt-3" href="/">"Back"</a>
+        </div>
+        <div class="col-start-2 row-start-1 justify-self-center pt-5 w-full px-12">
+            <h2 class="text-center font-bold leading-12">Sent and failed per day</h2>
+            <div class="grid grid-cols-4 text-center">
+                <p class="font-bold">Day</p>
+                <p class="font-bold">Reminders</p>
+                <p class="font-bold">ntfy</p>
+                <p class="font-bold">Hackatime</p>
+                {data
+                    .days
+                    .into_iter()
+                    .rev()
+                    .map(|d| {
+                        let cell = |(ok, failed): (i64, i64)| {
+                            let class = if failed > 0 { "text-red-400" } else { "" };
+                            view! {
+                                <p class=class>
+                                    {format!("{} / {}", ok, failed)}
+                                </p>
+                            }
+                        };
+                        view! {
+                            <p>{d.day.format("%d.%m.").to_string()}</p>
+                            {cell(d.reminders)}
+                            {cell(d.ntfy)}
+                            {cell(d.hackatime)}
+                        }
+                    })
+                    .collect_view()}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn Inspector(user: AdminUser, set_disabled: impl Fn(String, bool) + Copy + Send + 'static) -> impl IntoView {
+    let username = user.username.clone();
+    let disabled = user.disabled;
+
+    view! {
+        <h2 class="text-center font-bold leading-12 pt-3">{user.username.clone()}</h2>
+        <p class="text-center">
+            {format!(
+                "{}{}{}",
+                if user.email.is_empty() { "No email".to_string() } else { user.email.clone() },
+                if user.role == Role::Admin { ", admin" } else { "" },
+                if user.disabled { ", disabled" } else { "" },
+            )}
+        </p>
+        <p class="text-center">
+            {if user.tracked.is_empty() {
+                "Not tracking a project".to_string()
+            } else {
+                format!("Tracking {}", user.tracked.join(", "))
+            }}
+        </p>
+        <h2 class="text-center font-bold leading-12 pt-3">"Schedule (UTC)"</h2>
+        <div class="grid grid-cols-3 text-center">
+            {DAYS
+                .iter()
+                .zip(user.settings.slots())
+                .map(|(day, slot)| {
+                    let minute = slot.time.rem_euclid(WEEK);
+                    view! {
+                        <p>{*day}</p>
+                        <p>{format!("{:02}:{:02}", minute / 60 % 24, minute % 60)}</p>
+                        <p>{format!("{}h, {:?}", slot.goal, slot.window)}</p>
+                    }
+                })
+                .collect_view()}
+        </div>
+        <h2 class="text-center font-bold leading-12 pt-3">Last evaluation</h2>
+        <p class="text-center">
+            {match user.evaluation {
+                Some(e) => format!(
+                    "{} reminder at {}: {:.1}h of {:.1}h since {}",
+                    DAYS.get(e.day as usize).unwrap_or(&"?"),
+                    e.time.format("%d.%m. %H:%M"),
+                    e.seconds as f64 / 3600.0,
+                    e.target as f64 / 3600.0,
+                    e.window_start.format("%d.%m. %H:%M"),
+                ),
+                None => "None yet".to_string(),
+            }}
+        </p>
+        <h2 class="text-center font-bold leading-12 pt-3">Last notifications</h2>
+        {user
+            .notifications
+            .into_iter()
+            .map(|n| {
+                let class = if n.ok { "text-center" } else { "text-center text-red-400" };
+                view! {
+                    <p class=class>
+                        {format!("{} {}", n.time.format("%d.%m. %H:%M"), n.detail)}
+                    </p>
+                }
+            })
+            .collect_view()}
+        <button class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" on:click=move |_| set_disabled(username.clone(), !disabled)>
+            {if disabled { "Enable account" } else { "Disable account" }}
+        </button>
+    }
+}
@@ -0,0 +1,317 @@
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveTime, Timelike};
+use leptos::{
+    ev::{Event, SubmitEvent},
+    prelude::*,
+    task::spawn_local,
+};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::HtmlInputElement;
+
+use crate::{
+    add_calendar, add_vacation,
+    datatypes::{CalendarFile, Override, Vacation},
+    query_calendar, remove_calendar, remove_override, remove_vacation, set_override,
+};
+
+#[component]
+pub fn CalendarPage() -> impl IntoView {
+    let today = Local::now().date_naive();
+    let (month, set_month) = signal(today.with_day(1).unwrap());
+    let selected = RwSignal::new(today);
+
+    let vacations = RwSignal::new(Vec::<Vacation>::new());
+    let overrides = RwSignal::new(Vec::<Override>::new());
+    let calendars = RwSignal::new(Vec::<CalendarFile>::new());
+    let (error, set_error) = signal("".to_string());
+
+    let override_time = RwSignal::new("18:00".to_string());
+    let override_goal = RwSignal::new("1".to_string());
+    let vacation_start = RwSignal::new(today.to_string());
+    let vacation_end = RwSignal::new(today.to_string());
+    let calendar_name = RwSignal::new("".to_string());
+    let calendar_url = RwSignal::new("".to_string());
+    let calendar_content = RwSignal::new("".to_string());
+
+    let calendar_loader = Resource::new(|| (), |_| query_calendar());
+
+    Effect::new(move || {
+        if let Some(Ok(d)) = calendar_loader.get() {
+            vacations.set(d.vacations);
+            overrides.set(d.overrides);
+            calendars.set(d.calendars);
+        }
+    });
+
+    let on_vacation =
+        move |date: NaiveDate| vacations.get().iter().any(|v| v.start <= date && date <= v.end);
+    let day_override = move |date: NaiveDate| overrides.get().into_iter().find(|o| o.date == date);
+
+    let select = move |date: NaiveDate| {
+        selected.set(date);
+        vacation_start.set(date.to_string());
+        vacation_end.set(date.to_string());
+        if let Some(o) = day_override(date) {
+            override_time.set(format!("{:02}:{:02}", o.time / 60, o.time % 60));
+            override_goal.set(o.goal.to_string());
+        }
+    };
+
+    let save_override = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let time = match NaiveTime::parse_from_str(&override_time.get(), "%H:%M") {
+            Ok(t) => (t.hour() * 60 + t.minute()) as i32,
+            Err(_) => {
+                set_error.set("Pick a time for the day".to_string());
+                return;
+            }
+        };
+        let goal = match override_goal.get().trim().parse() {
+            Ok(g) => g,
+            Err(_) => {
+                set_error.set("Enter the goal in whole hours".to_string());
+                return;
+            }
+        };
+        let day = Override {
+            date: selected.get(),
+            time,
+            goal,
+        };
+        spawn_local(async move {
+            match set_override(day.clone()).await {
+                Ok(_) => {
+                    overrides.update(|list| {
+                        list.retain(|o| o.date != day.date);
+                        list.push(day);
+                    });
+                    set_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let new_vacation = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let (start, end) = match (
+            vacation_start.get().parse::<NaiveDate>(),
+            vacation_end.get().parse::<NaiveDate>(),
+        ) {
+            (Ok(s), Ok(e)) => (s, e),
+            _ => return,
+        };
+        spawn_local(async move {
+            match add_vacation(start, end).await {
+                Ok(v) => {
+                    vacations.update(|list| list.push(v));
+                    set_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let read_file = move |ev: Event| {
+        let input: HtmlInputElement = event_target(&ev);
+        let file = match input.files().and_then(|f| f.get(0)) {
+            Some(f) => f,
+            None => return,
+        };
+        if calendar_name.get().is_empty() {
+            calendar_name.set(file.name());
+        }
+        spawn_local(async move {
+            if let Ok(text) = JsFuture::from(file.text()).await {
+                calendar_content.set(text.as_string().unwrap_or_default());
+            }
+        });
+    };
+
+    let import_calendar = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        spawn_local(async move {
+            match add_calendar(
+                calendar_name.get_untracked(),
+                calendar_url.get_untracked(),
+                calendar_content.get_untracked(),
+            )
+            .await
+            {
+                Ok(c) => {
+                    calendars.update(|list| list.push(c));
+                    calendar_name.set("".to_string());
+                    calendar_url.set("".to_string());
+                    calendar_content.set("".to_string());
+                    set_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let days = move || {
+        let first = month.get();
+        let next = first.checked_add_months(Months::new(1)).unwrap();
+        let blanks = first.weekday().num_days_from_monday() as i64;
+        (-blanks..(next - first).num_days())
+            .map(|i| (i >= 0).then(|| first + Duration::days(i)))
+            .collect::<Vec<_>>()
+    };
+
+    view! {
+        <div class="col-start-1 row-start-1 justify-self-center pt-5">
+            <div class="flex justify-between items-center leading-12 mb-3">
+                <button class="bg-zinc-700 hover:bg-zinc-600 rounded-[3rem] w-12 h-12" on:click=move |_| set_month.update(|m| *m = m.checked_sub_months(Months::new(1)).unwrap())>"<"</button>
+                <p class="font-bold">{move || month.get().format("%B %Y").to_string()}</p>
+                <button class="bg-zinc-700 hover:bg-zinc-600 rounded-[3rem] w-12 h-12" on:click=move |_| set_month.update(|m| *m = m.checked_add_months(Months::new(1)).unwrap())>">"</button>
+            </div>
+            <div class="grid grid-cols-7 gap-1 text-center">
+                {["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].map(|d| view! { <p class="leading-12">{d}</p> })}
+                {move || {
+                    days()
+                        .into_iter()
+                        .map(|day| match day {
+                            None => view! { <p></p> }.into_any(),
+                            Some(date) => {
+                                view! {
+                                    <button
+                                        class="h-12 w-12 rounded-[3rem] bg-zinc-700 hover:bg-zinc-600"
+                                        class:bg-sky-700=move || on_vacation(date)
+                                        class:bg-yellow-600=move || day_override(date).is_some()
+                                        class:ring-2=move || selected.get() == date
+                                        on:click=move |_| select(date)
+                                    >
+                                        {date.day()}
+                                    </button>
+                                }
+                                    .into_any()
+                            }
+                        })
+                        .collect_view()
+                }}
+            </div>
+            <p class="text-center pt-3">"Blue days are vacations, yellow days have their own reminder."</p>
+            <a class="underline block text-center pt-3" href="/">"Back"</a>
+        </div>
+        <div class="col-start-2 row-start-1 justify-self-center pt-5">
+            <h2 class="text-center font-bold leading-12">{move || selected.get().format("%A, %d.%m.%Y").to_string()}</h2>
+            <form on:submit=save_override>
+                <div class="grid grid-cols-[repeat(4,25%)]">
+                    <p class="col-start-1 h-12 leading-12">Time:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="override_time" type="time" bind:value=override_time />
+                    <p class="col-start-3 h-12 leading-12 text-center">Goal:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="override_goal" type="number" bind:value=override_goal />
+                </div>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Set reminder for this day" />
+            </form>
+            <form class="pt-5" on:submit=new_vacation>
+                <div class="grid grid-cols-[repeat(4,25%)]">
+                    <p class="col-start-1 h-12 leading-12">From:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="vacation_start" type="date" bind:value=vacation_start />
+                    <p class="col-start-3 h-12 leading-12 text-center">To:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="vacation_end" type="date" bind:value=vacation_end />
+                </div>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Add vacation" />
+            </form>
+            <form class="pt-5" on:submit=import_calendar>
+                <div class="grid grid-cols-[25%_75%]">
+                    <p class="col-start-1 h-12 leading-12">Name:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="calendar_name" bind:value=calendar_name />
+                    <p class="col-start-1 h-12 leading-12">File:</p>
+                    <input class="col-start-2 h-12 leading-12 m-[0.25rem]" name="calendar_file" type="file" accept=".ics,text/calendar" on:change=read_file />
+                    <p class="col-start-1 h-12 leading-12">or URL:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="calendar_url" placeholder="https://..." bind:value=calendar_url />
+                </div>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Import calendar" />
+            </form>
+            <p class="text-center pt-3">"Holidays skip reminders, busy events delay them until they're over."</p>
+            <p class="text-center text-red-400 pt-3">{error}</p>
+        </div>
+        <div class="col-start-3 row-start-1 justify-self-center pt-5 overflow-scroll w-full pr-12">
+            <h2 class="text-center font-bold leading-12">Vacations</h2>
+            <ul>
+                {move || {
+                    vacations
+                        .get()
+                        .into_iter()
+                        .map(|v| {
+                            view! {
+                                <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3">
+                                    <p>{format!("{} - {}", v.start.format("%d.%m.%Y"), v.end.format("%d.%m.%Y"))}</p>
+                                    <button
+                                        class="hover:text-red-400"
+                                        on:click=move |_| {
+                                            vacations.update(|list| list.retain(|l| l.id != v.id));
+                                            spawn_local(async move {
+                                                let _ = remove_vacation(v.id).await;
+                                            });
+                                        }
+                                    >
+                                        "Remove"
+                                    </button>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+            <h2 class="text-center font-bold leading-12">Calendars</h2>
+            <ul>
+                {move || {
+                    calendars
+                        .get()
+                        .into_iter()
+                        .map(|c| {
+                            view! {
+                                <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3">
+                                    <p>{format!("{} ({} events)", c.name, c.events)}</p>
+                                    <button
+                                        class="hover:text-red-400"
+                                        on:click=move |_| {
+                                            calendars.update(|list| list.retain(|l| l.id != c.id));
+                                            spawn_local(async move {
+                                                let _ = remove_calendar(c.id).await;
+                                            });
+                                        }
+                                    >
+                                        "Remove"
+                                    </button>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+            <h2 class="text-center font-bold leading-12">Changed days</h2>
+            <ul>
+                {move || {
+                    overrides
+                        .get()
+                        .into_iter()
+                        .map(|o| {
+                            view! {
+                                <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3">
+                                    <p>{format!("{} {:02}:{:02}, {}h", o.date.format("%d.%m.%Y"), o.time / 60, o.time % 60, o.goal)}</p>
+                                    <button
+                                        class="hover:text-red-400"
+                                        on:click=move |_| {
+                                            overrides.update(|list| list.retain(|l| l.date != o.date));
+                                            spawn_local(async move {
+                                                let _ = remove_override(o.date).await;
+                                            });
+                                        }
+                                    >
+                                        "Remove"
+                                    </button>
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+        </div>
+    }
+}
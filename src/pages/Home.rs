@@ -1,8 +1,20 @@
-use chrono::{Datelike, Days, Duration, FixedOffset, Local, NaiveTime, Offset, Utc};
+use chrono::{Datelike, Days, Duration, FixedOffset, Local, NaiveDate, NaiveTime, Offset, Utc};
 use gloo_timers::callback::Interval;
 use leptos::{ev::SubmitEvent, logging::log, prelude::*, task::spawn_local};
 
-use crate::{datatypes::Settings, query_data, set_project, set_times};
+use crate::{
+    add_alias, add_checklist_item, add_milestone, confirm_link, create_api_token,
+    datatypes::{
+        Alias, AliasKind, ApiScope, Carry, CarryMode, Channels, ChecklistItem, LongGoal, Milestone,
+        Pace, ProjectCarry, Settings, Slot, Submission, Window,
+    },
+    list_api_tokens, list_sessions, logout, oidc_enabled, pending_link, query_data, query_week,
+    remove_alias,
+    remove_checklist_item, remove_milestone, request_code, revoke_api_token, revoke_session,
+    schedule::{format_offsets, parse_offsets, week_of},
+    set_carry, set_channels, set_checklist_item_done, set_deadline_alerts, set_long_goal,
+    set_milestone_done, set_project_carry, set_projects, set_shipped, set_times, verify_code,
+};
 
 #[component]
 pub fn HomePage() -> impl IntoView {
@@ -17,24 +29,164 @@ pub fn HomePage() -> impl IntoView {
     let (local_submit, set_local_submit) = signal("04:00".to_string());
     let (time, set_time) = signal(Local::now().format("%H:%M:%S").to_string());
     let (username, set_username) = signal("".to_string());
-    let (primary, set_primary) = signal("".to_string());
+    let (tracked, set_tracked) = signal(Vec::<String>::new());
 
     let monday = RwSignal::new("18:00".to_string());
     let monday_goal = RwSignal::new("1".to_string());
+    let monday_window = RwSignal::new("0".to_string());
     let tuesday = RwSignal::new("18:00".to_string());
     let tuesday_goal = RwSignal::new("1".to_string());
+    let tuesday_window = RwSignal::new("0".to_string());
     let wednesday = RwSignal::new("18:00".to_string());
     let wednesday_goal = RwSignal::new("1".to_string());
+    let wednesday_window = RwSignal::new("0".to_string());
     let thursday = RwSignal::new("18:00".to_string());
     let thursday_goal = RwSignal::new("1".to_string());
+    let thursday_window = RwSignal::new("0".to_string());
     let friday = RwSignal::new("18:00".to_string());
     let friday_goal = RwSignal::new("1".to_string());
+    let friday_window = RwSignal::new("0".to_string());
     let saturday = RwSignal::new("18:00".to_string());
     let saturday_goal = RwSignal::new("3".to_string());
+    let saturday_window = RwSignal::new("0".to_string());
     let sunday = RwSignal::new("18:00".to_string());
     let sunday_goal = RwSignal::new("3".to_string());
+    let sunday_window = RwSignal::new("3".to_string());
 
-    let project_loader = Resource::new(move || username.get(), |username| query_data(username));
+    let carry_mode = RwSignal::new("0".to_string());
+    let bank_cap = RwSignal::new("0".to_string());
+    let (balance, set_balance) = signal((0i64, 0i64));
+    let (carry_error, set_carry_error) = signal("".to_string());
+
+    let (feed_token, set_feed_token) = signal("".to_string());
+    let (pace, set_pace) = signal(None::<Pace>);
+
+    let goal_hours = RwSignal::new("0".to_string());
+    let goal_project = RwSignal::new("".to_string());
+    let goal_start = RwSignal::new(Local::now().date_naive().to_string());
+    let goal_end = RwSignal::new((Local::now().date_naive() + Duration::weeks(6)).to_string());
+    let (goal_error, set_goal_error) = signal("".to_string());
+
+    let checklist = RwSignal::new(Vec::<ChecklistItem>::new());
+    let checklist_project = RwSignal::new("".to_string());
+    let checklist_name = RwSignal::new("".to_string());
+    let (checklist_error, set_checklist_error) = signal("".to_string());
+
+    let milestones = RwSignal::new(Vec::<Milestone>::new());
+    let milestone_project = RwSignal::new("".to_string());
+    let milestone_name = RwSignal::new("".to_string());
+    let milestone_target = RwSignal::new("".to_string());
+    let milestone_due = RwSignal::new((Local::now().date_naive() + Duration::weeks(1)).to_string());
+    let milestone_offsets = RwSignal::new("3,1".to_string());
+    let (milestone_error, set_milestone_error) = signal("".to_string());
+
+    let deadline_alerts = RwSignal::new("24h, 6h, 1h, 15m".to_string());
+    let (alerts_error, set_alerts_error) = signal("".to_string());
+    let submission = RwSignal::new(None::<Submission>);
+    let submission_url = RwSignal::new("".to_string());
+    let (submission_error, set_submission_error) = signal("".to_string());
+
+    let ntfy_topic = RwSignal::new("".to_string());
+    let (topic_error, set_topic_error) = signal("".to_string());
+
+    let token_name = RwSignal::new("".to_string());
+    let token_scope = RwSignal::new("0".to_string());
+    let (new_token, set_new_token) = signal("".to_string());
+    let (token_error, set_token_error) = signal("".to_string());
+
+    let project_carry = RwSignal::new("0".to_string());
+    let project_carry_days = RwSignal::new("7".to_string());
+    let (project_carry_error, set_project_carry_error) = signal("".to_string());
+
+    let aliases = RwSignal::new(Vec::<Alias>::new());
+    let alias_project = RwSignal::new("".to_string());
+    let alias_kind = RwSignal::new("0".to_string());
+    let alias_pattern = RwSignal::new("".to_string());
+    let (alias_error, set_alias_error) = signal("".to_string());
+
+    let login_code = RwSignal::new("".to_string());
+    let login_topic = RwSignal::new("".to_string());
+    let (code_sent, set_code_sent) = signal(false);
+    let (login_error, set_login_error) = signal("".to_string());
+
+    let project_loader = Resource::new(|| (), |_| query_data());
+    let sessions_loader = Resource::new(|| (), |_| list_sessions());
+    let oidc_loader = Resource::new(|| (), |_| oidc_enabled());
+    let link_loader = Resource::new(|| (), |_| pending_link());
+    let tokens_loader = Resource::new(|| (), |_| list_api_tokens());
+    let logged_in = move || matches!(project_loader.get(), Some(Ok(_)));
+
+    let send_code = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let username = username.get();
+        let topic = login_topic.get();
+        spawn_local(async move {
+            match request_code(username, topic).await {
+                Ok(_) => {
+                    set_code_sent.set(true);
+                    set_login_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_login_error.set(e),
+                Err(e) => set_login_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let login = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let username = username.get();
+        let code = login_code.get();
+        spawn_local(async move {
+            match verify_code(username, code).await {
+                Ok(_) => {
+                    project_loader.refetch();
+                    sessions_loader.refetch();
+                    link_loader.refetch();
+                    login_code.set("".to_string());
+                    set_code_sent.set(false);
+                    set_login_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_login_error.set(e),
+                Err(e) => set_login_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let finish_link = move |confirm: bool| {
+        spawn_local(async move {
+            match confirm_link(confirm).await {
+                Ok(_) => set_login_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_login_error.set(e),
+                Err(e) => set_login_error.set(e.to_string()),
+            }
+            link_loader.refetch();
+        });
+    };
+
+    let log_out = move |_| {
+        spawn_local(async move {
+            let _ = logout().await;
+            project_loader.refetch();
+            sessions_loader.refetch();
+        });
+    };
+
+    let week = RwSignal::new("".to_string());
+    // None for the current week, which is shown with the editable project list
+    let selected_week = move || {
+        NaiveDate::parse_from_str(&format!("{}-1", week.get()), "%G-W%V-%u")
+            .ok()
+            .filter(|w| *w != week_of(Utc::now().naive_utc()))
+    };
+    let week_loader = Resource::new(
+        selected_week,
+        |week| async move {
+            match week {
+                Some(week) => query_week(week).await.map(Some),
+                None => Ok(None),
+            }
+        },
+    );
 
     let to_utc =
         move |orig_time: String, days: u64| match NaiveTime::parse_from_str(&orig_time, "%H:%M") {
@@ -64,65 +216,316 @@ pub fn HomePage() -> impl IntoView {
     let update = move |ev: SubmitEvent| {
         ev.prevent_default();
         let settings = Settings {
-            monday: (
-                to_utc(monday.get(), 0),
-                monday_goal.get().parse().expect("Leave the inputs"),
-            ),
-            tuesday: (
-                to_utc(tuesday.get(), 1),
-                tuesday_goal.get().parse().expect("Leave the inputs"),
-            ),
-            wednesday: (
-                to_utc(wednesday.get(), 2),
-                wednesday_goal.get().parse().expect("Leave the inputs"),
-            ),
-            thursday: (
-                to_utc(thursday.get(), 3),
-                thursday_goal.get().parse().expect("Leave the inputs"),
-            ),
-            friday: (
-                to_utc(friday.get(), 4),
-                friday_goal.get().parse().expect("Leave the inputs"),
-            ),
-            saturday: (
-                to_utc(saturday.get(), 5),
-                saturday_goal.get().parse().expect("Leave the inputs"),
-            ),
-            sunday: (
-                to_utc(sunday.get(), 6),
-                sunday_goal.get().parse().expect("Leave the inputs"),
-            ),
+            monday: Slot {
+                time: to_utc(monday.get(), 0),
+                goal: monday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(monday_window.get().parse().unwrap_or(0)),
+            },
+            tuesday: Slot {
+                time: to_utc(tuesday.get(), 1),
+                goal: tuesday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(tuesday_window.get().parse().unwrap_or(0)),
+            },
+            wednesday: Slot {
+                time: to_utc(wednesday.get(), 2),
+                goal: wednesday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(wednesday_window.get().parse().unwrap_or(0)),
+            },
+            thursday: Slot {
+                time: to_utc(thursday.get(), 3),
+                goal: thursday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(thursday_window.get().parse().unwrap_or(0)),
+            },
+            friday: Slot {
+                time: to_utc(friday.get(), 4),
+                goal: friday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(friday_window.get().parse().unwrap_or(0)),
+            },
+            saturday: Slot {
+                time: to_utc(saturday.get(), 5),
+                goal: saturday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(saturday_window.get().parse().unwrap_or(0)),
+            },
+            sunday: Slot {
+                time: to_utc(sunday.get(), 6),
+                goal: sunday_goal.get().parse().expect("Leave the inputs"),
+                window: Window::from_i64(sunday_window.get().parse().unwrap_or(0)),
+            },
+            offset: offset.get().local_minus_utc(),
         };
 
-        let username = username.get();
         spawn_local(async move {
-            set_times(username, settings).await;
+            set_times(settings).await;
         });
     };
 
     let load_settings = move |settings: Settings| {
-        monday.set(from_utc(settings.monday.0));
-        monday_goal.set(settings.monday.1.to_string());
-        tuesday.set(from_utc(settings.tuesday.0));
-        tuesday_goal.set(settings.tuesday.1.to_string());
-        wednesday.set(from_utc(settings.wednesday.0));
-        wednesday_goal.set(settings.wednesday.1.to_string());
-        thursday.set(from_utc(settings.thursday.0));
-        thursday_goal.set(settings.thursday.1.to_string());
-        friday.set(from_utc(settings.friday.0));
-        friday_goal.set(settings.friday.1.to_string());
-        saturday.set(from_utc(settings.saturday.0));
-        saturday_goal.set(settings.saturday.1.to_string());
-        sunday.set(from_utc(settings.sunday.0));
-        sunday_goal.set(settings.sunday.1.to_string());
+        monday.set(from_utc(settings.monday.time));
+        monday_goal.set(settings.monday.goal.to_string());
+        monday_window.set(settings.monday.window.to_i64().to_string());
+        tuesday.set(from_utc(settings.tuesday.time));
+        tuesday_goal.set(settings.tuesday.goal.to_string());
+        tuesday_window.set(settings.tuesday.window.to_i64().to_string());
+        wednesday.set(from_utc(settings.wednesday.time));
+        wednesday_goal.set(settings.wednesday.goal.to_string());
+        wednesday_window.set(settings.wednesday.window.to_i64().to_string());
+        thursday.set(from_utc(settings.thursday.time));
+        thursday_goal.set(settings.thursday.goal.to_string());
+        thursday_window.set(settings.thursday.window.to_i64().to_string());
+        friday.set(from_utc(settings.friday.time));
+        friday_goal.set(settings.friday.goal.to_string());
+        friday_window.set(settings.friday.window.to_i64().to_string());
+        saturday.set(from_utc(settings.saturday.time));
+        saturday_goal.set(settings.saturday.goal.to_string());
+        saturday_window.set(settings.saturday.window.to_i64().to_string());
+        sunday.set(from_utc(settings.sunday.time));
+        sunday_goal.set(settings.sunday.goal.to_string());
+        sunday_window.set(settings.sunday.window.to_i64().to_string());
+    };
+
+    let update_carry = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let mode = CarryMode::from_i64(carry_mode.get().parse().unwrap_or(0));
+        let cap = match bank_cap.get().trim().parse() {
+            Ok(c) => c,
+            Err(_) => {
+                set_carry_error.set("Enter the bank limit in whole hours".to_string());
+                return;
+            }
+        };
+        spawn_local(async move {
+            match set_carry(mode, cap).await {
+                Ok(_) => set_carry_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_carry_error.set(e),
+                Err(e) => set_carry_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let load_carry = move |carry: Carry| {
+        carry_mode.set(carry.mode.to_i64().to_string());
+        bank_cap.set(carry.cap.to_string());
+        set_balance.set((carry.balance, carry.bank));
+    };
+
+    let update_project_carry = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let days = match project_carry_days.get().trim().parse() {
+            Ok(d) => d,
+            Err(_) => {
+                set_project_carry_error.set("Enter the days as a whole number".to_string());
+                return;
+            }
+        };
+        let carry = ProjectCarry::from_i64(project_carry.get().parse().unwrap_or(0), days);
+        spawn_local(async move {
+            match set_project_carry(carry).await {
+                Ok(_) => set_project_carry_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_project_carry_error.set(e),
+                Err(e) => set_project_carry_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let update_long_goal = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let hours: i32 = match goal_hours.get().trim().parse() {
+            Ok(h) => h,
+            Err(_) => {
+                set_goal_error.set("Enter the goal in whole hours, 0 turns it off".to_string());
+                return;
+            }
+        };
+        let goal = match (goal_start.get().parse(), goal_end.get().parse()) {
+            _ if hours <= 0 => None,
+            (Ok(start), Ok(end)) => Some(LongGoal {
+                hours,
+                project: goal_project.get(),
+                start,
+                end,
+            }),
+            _ => {
+                set_goal_error.set("Pick the start and end of the event".to_string());
+                return;
+            }
+        };
+        spawn_local(async move {
+            match set_long_goal(goal).await {
+                Ok(_) => set_goal_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_goal_error.set(e),
+                Err(e) => set_goal_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let update_deadline_alerts = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let alerts = match parse_offsets(&deadline_alerts.get()) {
+            Some(a) => a,
+            None => {
+                set_alerts_error.set("Use durations like 24h, 6h, 1h 30m, 15m".to_string());
+                return;
+            }
+        };
+        spawn_local(async move {
+            match set_deadline_alerts(alerts).await {
+                Ok(_) => set_alerts_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_alerts_error.set(e),
+                Err(e) => set_alerts_error.set(e.to_string()),
+            }
+        });
+    };
+
+    // accounts from before secret topics still send to the username
+    let topic = move || match ntfy_topic.get() {
+        t if t.trim().is_empty() => username.get(),
+        t => t.trim().to_string(),
+    };
+
+    let update_channels = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let channels = Channels { ntfy_topic: ntfy_topic.get() };
+        spawn_local(async move {
+            match set_channels(channels).await {
+                Ok(_) => set_topic_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_topic_error.set(e),
+                Err(e) => set_topic_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let new_api_token = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let name = token_name.get();
+        let scope = ApiScope::from_i64(token_scope.get().parse().unwrap_or(0));
+        spawn_local(async move {
+            match create_api_token(name, scope).await {
+                Ok(secret) => {
+                    set_new_token.set(secret);
+                    token_name.set("".to_string());
+                    set_token_error.set("".to_string());
+                    tokens_loader.refetch();
+                }
+                Err(ServerFnError::ServerError(e)) => set_token_error.set(e),
+                Err(e) => set_token_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let save_shipped = move |shipped: bool| {
+        let url = submission_url.get();
+        spawn_local(async move {
+            match set_shipped(shipped, url).await {
+                Ok(s) => {
+                    submission.set(s);
+                    submission_url.set("".to_string());
+                    set_submission_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_submission_error.set(e),
+                Err(e) => set_submission_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let new_checklist_item = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let project = checklist_project.get();
+        let name = checklist_name.get();
+        spawn_local(async move {
+            match add_checklist_item(project, name).await {
+                Ok(i) => {
+                    checklist.update(|list| list.push(i));
+                    checklist_name.set("".to_string());
+                    set_checklist_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_checklist_error.set(e),
+                Err(e) => set_checklist_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let new_milestone = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let due = match milestone_due.get().parse() {
+            Ok(d) => d,
+            Err(_) => return,
+        };
+        let milestone = Milestone {
+            id: 0,
+            project: milestone_project.get(),
+            name: milestone_name.get(),
+            target: milestone_target.get().parse().ok(),
+            due,
+            offsets: milestone_offsets
+                .get()
+                .split(',')
+                .filter_map(|o| o.trim().parse().ok())
+                .collect(),
+            done: false,
+            progress: 0,
+        };
+        spawn_local(async move {
+            match add_milestone(milestone).await {
+                Ok(m) => {
+                    milestones.update(|list| {
+                        list.push(m);
+                        list.sort_by_key(|l| l.due);
+                    });
+                    milestone_name.set("".to_string());
+                    set_milestone_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_milestone_error.set(e),
+                Err(e) => set_milestone_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let new_alias = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let project = alias_project.get();
+        let kind = AliasKind::from_i64(alias_kind.get().parse().unwrap_or(0));
+        let pattern = alias_pattern.get();
+        spawn_local(async move {
+            match add_alias(project, kind, pattern).await {
+                Ok(a) => {
+                    aliases.update(|list| list.push(a));
+                    alias_pattern.set("".to_string());
+                    set_alias_error.set("".to_string());
+                }
+                Err(ServerFnError::ServerError(e)) => set_alias_error.set(e),
+                Err(e) => set_alias_error.set(e.to_string()),
+            }
+        });
     };
 
     Effect::new(move || {
         if let Some(res) = project_loader.get() {
             match res {
                 Ok(d) => {
-                    set_primary.set(d.primary);
+                    alias_project.set(d.tracked.first().cloned().unwrap_or_default());
+                    milestone_project.set(d.tracked.first().cloned().unwrap_or_default());
+                    milestones.set(d.milestones);
+                    checklist_project.set(d.tracked.first().cloned().unwrap_or_default());
+                    checklist.set(d.checklist);
+                    set_tracked.set(d.tracked);
+                    aliases.set(d.aliases);
                     load_settings(d.settings);
+                    load_carry(d.carry);
+                    project_carry.set(d.project_carry.to_i64().to_string());
+                    if let ProjectCarry::MostWorked(days) = d.project_carry {
+                        project_carry_days.set(days.to_string());
+                    }
+                    set_feed_token.set(d.feed_token);
+                    set_pace.set(d.pace);
+                    deadline_alerts.set(format_offsets(&d.deadline_alerts));
+                    submission.set(d.submission);
+                    ntfy_topic.set(d.channels.ntfy_topic);
+                    if let Some(g) = d.long_goal {
+                        goal_hours.set(g.hours.to_string());
+                        goal_project.set(g.project);
+                        goal_start.set(g.start.to_string());
+                        goal_end.set(g.end.to_string());
+                    }
                 }
                 Err(_) => (),
             }
@@ -151,28 +554,115 @@ pub fn HomePage() -> impl IntoView {
 
     view! {
         <div class="col-start-1 row-start-1 justify-self-center pt-5">
-            <form on:submit=move |ev| {
-                ev.prevent_default();
-                project_loader.refetch();
-            }>
-                <input
-                    class="bg-zinc-700 p-1 h-12 leading-12 w-full rounded-[3rem] text-center m-1 focus:outline-none mb-4"
-                    id="username"
-                    name="username"
-                    placeholder="Username"
-                    bind:value=(username, set_username)
-                />
-            </form>
+            {move || if logged_in() {
+                view! {
+                    <div class="grid grid-cols-[75%_25%] mb-4">
+                        <p class="col-start-1 h-12 leading-12 text-center">{move || format!("Logged in as {}", username.get())}</p>
+                        <button class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" on:click=log_out>"Log out"</button>
+                    </div>
+                    {move || match link_loader.get() {
+                        Some(Ok(Some(login))) => view! {
+                            <p class="text-center">{format!("Link the single sign-on login {} to this account?", login)}</p>
+                            <div class="grid grid-cols-2 mb-4">
+                                <button class="col-start-1 bg-zinc-700 text-center h12 leading-12 rounded-[3rem] hover:bg-zinc-600 m-1" on:click=move |_| finish_link(true)>"Link"</button>
+                                <button class="col-start-2 bg-zinc-700 text-center h12 leading-12 rounded-[3rem] hover:bg-zinc-600 m-1" on:click=move |_| finish_link(false)>"Don't link"</button>
+                            </div>
+                        }.into_any(),
+                        _ => view! {
+                            <Show when=move || matches!(oidc_loader.get(), Some(Ok(true)))>
+                                <a class="block underline text-center mb-4" href="/auth/oidc/login" rel="external">
+                                    "Link single sign-on to this account"
+                                </a>
+                            </Show>
+                        }.into_any(),
+                    }}
+                }.into_any()
+            } else if code_sent.get() {
+                view! {
+                    <form class="grid grid-cols-[75%_25%] mb-4" on:submit=login>
+                        <input class="col-start-1 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="code" placeholder="Code from your notifications" autocomplete="one-time-code" bind:value=login_code />
+                        <input class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Log in" />
+                    </form>
+                }.into_any()
+            } else {
+                view! {
+                    <form on:submit=send_code>
+                        <input
+                            class="bg-zinc-700 p-1 h-12 leading-12 w-full rounded-[3rem] text-center m-1 focus:outline-none mb-4"
+                            id="username"
+                            name="username"
+                            placeholder="Username, we'll send you a login code"
+                            bind:value=(username, set_username)
+                        />
+                        <div class="grid grid-cols-[75%_25%] mb-4">
+                            <input class="col-start-1 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="topic" placeholder="Secret ntfy topic, new accounts only" bind:value=login_topic />
+                            <input class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Send code" />
+                        </div>
+                    </form>
+                    <p class="text-center mb-4">"Codes go to your secret ntfy topic, subscribe to it before asking for one. New accounts pick it here."</p>
+                    <Show when=move || matches!(oidc_loader.get(), Some(Ok(true)))>
+                        <a class="block bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mb-4" href="/auth/oidc/login" rel="external">
+                            "Log in with single sign-on"
+                        </a>
+                    </Show>
+                }.into_any()
+            }}
+            <p class="text-center text-red-400">{login_error}</p>
             <form on:submit=update>
+                <div class="grid grid-cols-[repeat(5,20%)]">
+                    <p class="col-start-1 h-12 leading-12">Monday:</p><input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="monday" type="time" bind:value=monday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="monday_goal" type="number" bind:value=monday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="monday_window" prop:value=move || monday_window.get() on:change=move |ev| monday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                    <p class="col-start-1 h-12 leading-12">Tuesday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="tuesday" type="time" bind:value=tuesday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="tuesday_goal" type="number" bind:value=tuesday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="tuesday_window" prop:value=move || tuesday_window.get() on:change=move |ev| tuesday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                    <p class="col-start-1 h-12 leading-12">Wednesday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="wednesday" type="time" bind:value=wednesday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="wednesday_goal" type="number" bind:value=wednesday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="wednesday_window" prop:value=move || wednesday_window.get() on:change=move |ev| wednesday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                    <p class="col-start-1 h-12 leading-12">Thursday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="thursday" type="time" bind:value=thursday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="thursday_goal" type="number" bind:value=thursday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="thursday_window" prop:value=move || thursday_window.get() on:change=move |ev| thursday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                    <p class="col-start-1 h-12 leading-12">Friday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="friday" type="time" bind:value=friday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="friday_goal" type="number" bind:value=friday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="friday_window" prop:value=move || friday_window.get() on:change=move |ev| friday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                    <p class="col-start-1 h-12 leading-12">Saturday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="saturday" type="time" bind:value=saturday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="saturday_goal" type="number" bind:value=saturday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="saturday_window" prop:value=move || saturday_window.get() on:change=move |ev| saturday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                    <p class="col-start-1 h-12 leading-12">Sunday:</p><input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem] mb-3" name="sunday" type="time" bind:value=sunday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="sunday_goal" type="number" bind:value=sunday_goal /> <select class="col-start-5 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="sunday_window" prop:value=move || sunday_window.get() on:change=move |ev| sunday_window.set(event_target_value(&ev))><WindowOptions /></select>
+                </div>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Save" />
+            </form>
+            <form class="pt-5" on:submit=update_carry>
+                <div class="grid grid-cols-[repeat(4,25%)]">
+                    <p class="col-start-1 h-12 leading-12">Carry over:</p>
+                    <select class="col-start-2 col-span-3 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="carry_mode" prop:value=move || carry_mode.get() on:change=move |ev| carry_mode.set(event_target_value(&ev))>
+                        <option value="0">"Off"</option>
+                        <option value="1">"Spread over the week"</option>
+                        <option value="2">"Spread over the week and bank"</option>
+                    </select>
+                    <p class="col-start-1 col-span-2 h-12 leading-12">Bank limit (hours):</p> <input class="col-start-3 col-span-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem] mb-3" name="bank_cap" type="number" bind:value=bank_cap />
+                </div>
+                <p class="text-center pb-3">{move || {
+                    let (balance, bank) = balance.get();
+                    format!("Balance: {:+.1}h, bank: {:+.1}h", balance as f64 / 3600.0, bank as f64 / 3600.0)
+                }}</p>
+                <p class="text-center text-red-400">{carry_error}</p>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Save" />
+            </form>
+            <form class="pt-5" on:submit=update_project_carry>
+                <div class="grid grid-cols-[repeat(4,25%)]">
+                    <p class="col-start-1 h-12 leading-12">New week:</p>
+                    <select class="col-start-2 col-span-3 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="project_carry" prop:value=move || project_carry.get() on:change=move |ev| project_carry.set(event_target_value(&ev))>
+                        <option value="0">"Clear my projects"</option>
+                        <option value="1">"Keep my projects"</option>
+                        <option value="2">"Track my most worked on project"</option>
+                    </select>
+                    <p class="col-start-1 col-span-2 h-12 leading-12">Most worked of the last (days):</p> <input class="col-start-3 col-span-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem] mb-3" name="project_carry_days" type="number" bind:value=project_carry_days />
+                </div>
+                <p class="text-center text-red-400">{project_carry_error}</p>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Save" />
+            </form>
+            <form class="pt-5" on:submit=update_long_goal>
                 <div class="grid grid-cols-[repeat(4,25%)]">
-                    <p class="col-start-1 h-12 leading-12">Monday:</p><input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="monday" type="time" bind:value=monday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="monday_goal" type="number" bind:value=monday_goal />
-                    <p class="col-start-1 h-12 leading-12">Tuesday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="tuesday" type="time" bind:value=tuesday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="tuesday_goal" type="number" bind:value=tuesday_goal />
-                    <p class="col-start-1 h-12 leading-12">Wednesday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="wednesday" type="time" bind:value=wednesday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="wednesday_goal" type="number" bind:value=wednesday_goal />
-                    <p class="col-start-1 h-12 leading-12">Thursday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="thursday" type="time" bind:value=thursday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="thursday_goal" type="number" bind:value=thursday_goal />
-                    <p class="col-start-1 h-12 leading-12">Friday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="friday" type="time" bind:value=friday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="friday_goal" type="number" bind:value=friday_goal />
-                    <p class="col-start-1 h-12 leading-12">Saturday:</p><input class="fcol-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="saturday" type="time" bind:value=saturday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="saturday_goal" type="number" bind:value=saturday_goal />
-                    <p class="col-start-1 h-12 leading-12">Sunday:</p><input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem] mb-3" name="sunday" type="time" bind:value=sunday /> <p class="col-start-3 h-12 leading-12 text-center">Goal:</p> <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="sunday_goal" type="number" bind:value=sunday_goal />
+                    <p class="col-start-1 h-12 leading-12">Event goal:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="goal_hours" type="number" bind:value=goal_hours />
+                    <p class="col-start-3 h-12 leading-12 text-center">Project:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="goal_project" bind:value=goal_project />
+                    <p class="col-start-1 h-12 leading-12">From:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="goal_start" type="date" bind:value=goal_start />
+                    <p class="col-start-3 h-12 leading-12 text-center">To:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem] mb-3" name="goal_end" type="date" bind:value=goal_end />
                 </div>
+                <p class="text-center pb-3">"Hours on one project over the whole event, 0 turns it off."</p>
+                <p class="text-center text-red-400">{goal_error}</p>
                 <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Save" />
             </form>
         </div>
@@ -181,15 +671,138 @@ pub fn HomePage() -> impl IntoView {
                 <p class="text-center pt-5">Your current time is {time}</p>
                 <p class="text-center pt-2">If not please adjust the times accordingly.</p>
                 <p class="text-center pt-2">{move || format!("You'll have to submit at {}", local_submit.get())}</p>
+                <p class="text-center pt-2">{move || pace.get().map(|p| p.message())}</p>
+                {move || match submission.get() {
+                    Some(s) => view! {
+                        <div class="grid grid-cols-[75%_25%] pt-2">
+                            <p class="col-start-1 h-12 leading-12 text-center">
+                                "You shipped this week"
+                                {(!s.url.is_empty()).then(|| view! { ": " <a class="underline" href=s.url.clone()>{s.url.clone()}</a> })}
+                            </p>
+                            <button class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" on:click=move |_| save_shipped(false)>"Undo"</button>
+                        </div>
+                    }.into_any(),
+                    None => view! {
+                        <form class="grid grid-cols-[75%_25%] pt-2" on:submit=move |ev: SubmitEvent| { ev.prevent_default(); save_shipped(true) }>
+                            <input class="col-start-1 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="submission_url" type="url" placeholder="Submission link (optional)" bind:value=submission_url />
+                            <input class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Shipped!" />
+                        </form>
+                    }.into_any(),
+                }}
+                <p class="text-center text-red-400">{submission_error}</p>
+                <form class="grid grid-cols-[25%_50%_25%] pt-2" on:submit=update_deadline_alerts>
+                    <p class="col-start-1 h-12 leading-12">Alerts before:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="deadline_alerts" type="text" bind:value=deadline_alerts />
+                    <input class="col-start-3 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Save" />
+                </form>
+                <p class="text-center text-red-400">{alerts_error}</p>
+                <form class="grid grid-cols-[25%_50%_25%] pt-2" on:submit=update_channels>
+                    <p class="col-start-1 h-12 leading-12">ntfy topic:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="ntfy_topic" type="text" placeholder=move || username.get() bind:value=ntfy_topic />
+                    <input class="col-start-3 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Save" />
+                </form>
+                <p class="text-center text-red-400">{topic_error}</p>
+                <p class="text-center pt-2"><a class="underline" href="/calendar">"Vacations and single days"</a></p>
+                <p class="text-center pt-2"><a class="underline" href="/stats">"Stats"</a></p>
+                <p class="text-center pt-2"><a class="underline" href="/journal">"Devlog journal"</a></p>
+                <Show when=logged_in>
+                    <h2 class="text-center font-bold leading-12 pt-3">"Logged in on"</h2>
+                    <Suspense fallback=move || view! { <p class="text-center">"Loading..."</p> }>
+                        {move || {
+                            sessions_loader
+                                .get()
+                                .map(|res| match res {
+                                    Ok(list) => list
+                                        .into_iter()
+                                        .map(|s| {
+                                            view! {
+                                                <div class="grid grid-cols-[75%_25%]">
+                                                    <p class="col-start-1 h-12 leading-12 text-center">
+                                                        {format!(
+                                                            "Since {}, last used {}",
+                                                            s.created.format("%d.%m.%Y"),
+                                                            s.last_used.format("%d.%m. %H:%M"),
+                                                        )}
+                                                    </p>
+                                                    {if s.current {
+                                                        view! { <p class="col-start-2 h-12 leading-12 text-center">"This browser"</p> }.into_any()
+                                                    } else {
+                                                        view! {
+                                                            <button class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" on:click=move |_| {
+                                                                spawn_local(async move {
+                                                                    let _ = revoke_session(s.id).await;
+                                                                    sessions_loader.refetch();
+                                                                });
+                                                            }>"Log out"</button>
+                                                        }.into_any()
+                                                    }}
+                                                </div>
+                                            }
+                                        })
+                                        .collect_view()
+                                        .into_any(),
+                                    Err(_) => ().into_any(),
+                                })
+                        }}
+                    </Suspense>
+                    <h2 class="text-center font-bold leading-12 pt-3">"API tokens"</h2>
+                    <Suspense fallback=move || view! { <p class="text-center">"Loading..."</p> }>
+                        {move || {
+                            tokens_loader
+                                .get()
+                                .map(|res| match res {
+                                    Ok(list) => list
+                                        .into_iter()
+                                        .map(|t| {
+                                            view! {
+                                                <div class="grid grid-cols-[75%_25%]">
+                                                    <p class="col-start-1 h-12 leading-12 text-center">
+                                                        {format!(
+                                                            "{} ({}), last used {}",
+                                                            t.name,
+                                                            if t.scope == ApiScope::ReadWrite { "read-write" } else { "read-only" },
+                                                            t.last_used.map(|l| l.format("%d.%m. %H:%M").to_string()).unwrap_or("never".to_string()),
+                                                        )}
+                                                    </p>
+                                                    <button class="col-start-2 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" on:click=move |_| {
+                                                        spawn_local(async move {
+                                                            let _ = revoke_api_token(t.id).await;
+                                                            tokens_loader.refetch();
+                                                        });
+                                                    }>"Revoke"</button>
+                                                </div>
+                                            }
+                                        })
+                                        .collect_view()
+                                        .into_any(),
+                                    Err(_) => ().into_any(),
+                                })
+                        }}
+                    </Suspense>
+                    <form class="grid grid-cols-[50%_25%_25%] pt-2" on:submit=new_api_token>
+                        <input class="col-start-1 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="token_name" placeholder="Name" bind:value=token_name />
+                        <select class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="token_scope" prop:value=move || token_scope.get() on:change=move |ev| token_scope.set(event_target_value(&ev))>
+                            <option value="0">"Read"</option>
+                            <option value="1">"Read-write"</option>
+                        </select>
+                        <input class="col-start-3 bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600" type="submit" value="Create" />
+                    </form>
+                    <Show when=move || !new_token.get().is_empty()>
+                        <p class="text-center pt-2">"Copy it now, it won't be shown again:"</p>
+                        <p class="text-center font-mono break-all">{new_token}</p>
+                    </Show>
+                    <p class="text-center text-red-400">{token_error}</p>
+                </Show>
             </div>
             <div>
                 <h1 class="pt-5 text-[5rem] text-center font-bold">Tutorial</h1>
                 <ul>
                     <li class="text-center leading-7">Enter your hackatime username or slack id</li>
                     <li class="text-center leading-7">Input your desired notification times and goals</li>
-                    <li class="text-center leading-7">Select your project</li>
+                    <li class="text-center leading-7">Select your projects, the time of all selected projects counts</li>
                     <li class="text-center leading-7">Download <a class="underline" href="https://ntfy.sh">ntfy.sh</a> (web and mobile available)</li>
-                    <li class="text-center leading-7">Subscribe to <a class="underline" href={move || format!("https://ntfy.tim.hackclub.app/{}", username.get())}>{move || format!("https://ntfy.tim.hackclub.app/{}", username.get())}</a></li>
+                    <li class="text-center leading-7">Subscribe to <a class="underline" href={move || format!("https://ntfy.tim.hackclub.app/{}", topic())}>{move || format!("https://ntfy.tim.hackclub.app/{}", topic())}</a></li>
+                    <li class="text-center leading-7">Optionally add <a class="underline" href=move || format!("/feed/{}.ics", feed_token.get())>"your schedule"</a> to your calendar app</li>
                     <li class="text-center leading-7">{"Don't forget to save your setting"}</li>
                     <li class="text-center leading-7">If you have any problems ping or dm me on slack</li>
                     <li class="text-center leading-7">You can find me in the siege channel as Tim</li>
@@ -197,6 +810,73 @@ pub fn HomePage() -> impl IntoView {
             </div>
         </div>
         <div class="col-start-3 row-start-1 justify-self-center pt-5 overflow-scroll w-full pr-12">
+            <div class="grid grid-cols-[25%_75%] mb-3">
+                <p class="col-start-1 h-12 leading-12">Week:</p>
+                <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="week" type="week" bind:value=week />
+            </div>
+            <Suspense fallback=move || {
+                view! { <p>"Loading..."</p> }
+            }>
+                {move || {
+                    week_loader
+                        .get()
+                        .map(|res| match res {
+                            Ok(Some(w)) => {
+                                let names = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+                                view! {
+                                    <ul>
+                                        {w
+                                            .projects
+                                            .into_iter()
+                                            .map(|p| {
+                                                let tracked = w.tracked.contains(&p.name);
+                                                view! {
+                                                    <li
+                                                        class="w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3"
+                                                        class:bg-yellow-600=tracked
+                                                        class:bg-zinc-700=!tracked
+                                                    >
+                                                        <p>{p.name}</p>
+                                                        <p>{p.time}</p>
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                    <p class="text-center leading-12">
+                                        {match w.submission {
+                                            Some(s) if s.url.is_empty() => "Shipped".to_string(),
+                                            Some(s) => format!("Shipped: {}", s.url),
+                                            None => "Not shipped".to_string(),
+                                        }}
+                                    </p>
+                                    <h2 class="text-center font-bold leading-12">Goals</h2>
+                                    <ul>
+                                        {w
+                                            .days
+                                            .into_iter()
+                                            .map(|d| {
+                                                let met = d.seconds >= d.target;
+                                                view! {
+                                                    <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3">
+                                                        <p>{names[d.day as usize % 7]}</p>
+                                                        <p>{format!("{:.1}h / {:.1}h", d.seconds as f64 / 3600.0, d.target as f64 / 3600.0)}</p>
+                                                        <p class:text-green-400=met class:text-red-400=!met>{if met { "Met" } else { "Missed" }}</p>
+                                                    </li>
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </ul>
+                                }
+                                    .into_any()
+                            }
+                            Ok(None) => ().into_any(),
+                            Err(ServerFnError::ServerError(e)) => view! { <p>{e}</p> }.into_any(),
+                            Err(e) => view! { <p>{e.to_string()}</p> }.into_any(),
+                        })
+                }}
+            </Suspense>
+            <div class:hidden=move || selected_week().is_some()>
             <Suspense fallback=move || {
                 view! { <p>"Loading..."</p> }
             }>
@@ -218,26 +898,32 @@ pub fn HomePage() -> impl IntoView {
                                                                 class="bg-zinc-700 hover:bg-zinc-600 w-full hover:cursor-pointer flex justify-between items-center height-12 leading-12 rounded-[3rem] pl-12 pr-12 mb-3"
                                                                 class:bg-yellow-600={
                                                                     let name = p.name.clone();
-                                                                    move || primary.get() == name
+                                                                    move || tracked.get().contains(&name)
                                                                 }
                                                                 class:bg-zinc-700={
                                                                     let name = p.name.clone();
-                                                                    move || primary.get() != name
+                                                                    move || !tracked.get().contains(&name)
                                                                 }
                                                                 class:hover:bg-yellow-500={
                                                                     let name = p.name.clone();
-                                                                    move || primary.get() == name
+                                                                    move || tracked.get().contains(&name)
                                                                 }
                                                                 class:hover:bg-zinc-600={
                                                                     let name = p.name.clone();
-                                                                    move || primary.get() != name
+                                                                    move || !tracked.get().contains(&name)
                                                                 }
                                                                 on:click=move |_| {
-                                                                    let project = p.name.clone();
-                                                                    let username = username.get();
-                                                                    set_primary.set(p.name.clone());
+                                                                    set_tracked.update(|t| {
+                                                                        match t.iter().position(|n| *n == p.name) {
+                                                                            Some(i) => {
+                                                                                t.remove(i);
+                                                                            }
+                                                                            None => t.push(p.name.clone()),
+                                                                        }
+                                                                    });
+                                                                    let projects = tracked.get_untracked();
                                                                     spawn_local(async move {
-                                                                        set_project(username, project).await;
+                                                                        let _ = set_projects(projects).await;
                                                                     });
                                                                 }
                                                             >
@@ -264,6 +950,194 @@ pub fn HomePage() -> impl IntoView {
                         })
                 }}
             </Suspense>
+            </div>
+            <form class="pt-5" on:submit=new_checklist_item>
+                <h2 class="text-center font-bold leading-12">Checklist</h2>
+                <ul>
+                    {move || {
+                        let several = tracked.get().len() > 1;
+                        checklist
+                            .get()
+                            .into_iter()
+                            .map(|i| {
+                                let done = i.done;
+                                view! {
+                                    <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3" class:line-through=done>
+                                        <p>{if several { format!("{} ({})", i.name, i.project) } else { i.name.clone() }}</p>
+                                        <button
+                                            class="hover:text-green-400"
+                                            type="button"
+                                            on:click=move |_| {
+                                                checklist.update(|list| {
+                                                    if let Some(l) = list.iter_mut().find(|l| l.id == i.id) {
+                                                        l.done = !done;
+                                                    }
+                                                });
+                                                spawn_local(async move {
+                                                    let _ = set_checklist_item_done(i.id, !done).await;
+                                                });
+                                            }
+                                        >
+                                            {if done { "Undo" } else { "Done" }}
+                                        </button>
+                                        <button
+                                            class="hover:text-red-400"
+                                            type="button"
+                                            on:click=move |_| {
+                                                checklist.update(|list| list.retain(|l| l.id != i.id));
+                                                spawn_local(async move {
+                                                    let _ = remove_checklist_item(i.id).await;
+                                                });
+                                            }
+                                        >
+                                            "Remove"
+                                        </button>
+                                    </li>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </ul>
+                <div class="grid grid-cols-[repeat(4,25%)]">
+                    <p class="col-start-1 h-12 leading-12">Project:</p>
+                    <select class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="checklist_project" prop:value=move || checklist_project.get() on:change=move |ev| checklist_project.set(event_target_value(&ev))>
+                        {move || tracked.get().into_iter().map(|p| view! { <option value=p.clone()>{p.clone()}</option> }).collect_view()}
+                    </select>
+                    <p class="col-start-3 h-12 leading-12 text-center">Item:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="checklist_name" bind:value=checklist_name />
+                </div>
+                <p class="text-center text-red-400">{checklist_error}</p>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Add item" />
+            </form>
+            <form class="pt-5" on:submit=new_milestone>
+                <h2 class="text-center font-bold leading-12">Milestones</h2>
+                <ul>
+                    {move || {
+                        milestones
+                            .get()
+                            .into_iter()
+                            .map(|m| {
+                                let done = m.done;
+                                view! {
+                                    <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3" class:line-through=done>
+                                        <p>{format!("{} ({})", m.name, m.project)}</p>
+                                        <p>
+                                            {match m.target {
+                                                Some(t) => format!("{:.1}h / {}h, {}", m.progress as f64 / 3600.0, t, m.due.format("%d.%m.")),
+                                                None => m.due.format("%d.%m.").to_string(),
+                                            }}
+                                        </p>
+                                        <button
+                                            class="hover:text-green-400"
+                                            type="button"
+                                            on:click=move |_| {
+                                                milestones.update(|list| {
+                                                    if let Some(l) = list.iter_mut().find(|l| l.id == m.id) {
+                                                        l.done = !done;
+                                                    }
+                                                });
+                                                spawn_local(async move {
+                                                    let _ = set_milestone_done(m.id, !done).await;
+                                                });
+                                            }
+                                        >
+                                            {if done { "Undo" } else { "Done" }}
+                                        </button>
+                                        <button
+                                            class="hover:text-red-400"
+                                            type="button"
+                                            on:click=move |_| {
+                                                milestones.update(|list| list.retain(|l| l.id != m.id));
+                                                spawn_local(async move {
+                                                    let _ = remove_milestone(m.id).await;
+                                                });
+                                            }
+                                        >
+                                            "Remove"
+                                        </button>
+                                    </li>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </ul>
+                <div class="grid grid-cols-[repeat(4,25%)]">
+                    <p class="col-start-1 h-12 leading-12">Project:</p>
+                    <select class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="milestone_project" prop:value=move || milestone_project.get() on:change=move |ev| milestone_project.set(event_target_value(&ev))>
+                        {move || tracked.get().into_iter().map(|p| view! { <option value=p.clone()>{p.clone()}</option> }).collect_view()}
+                    </select>
+                    <p class="col-start-3 h-12 leading-12 text-center">Name:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="milestone_name" bind:value=milestone_name />
+                    <p class="col-start-1 h-12 leading-12">Hours:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="milestone_target" type="number" placeholder="optional" bind:value=milestone_target />
+                    <p class="col-start-3 h-12 leading-12 text-center">Due:</p>
+                    <input class="col-start-4 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="milestone_due" type="date" bind:value=milestone_due />
+                    <p class="col-start-1 col-span-2 h-12 leading-12">Remind (days before):</p>
+                    <input class="col-start-3 col-span-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="milestone_offsets" bind:value=milestone_offsets />
+                </div>
+                <p class="text-center text-red-400">{milestone_error}</p>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Add milestone" />
+            </form>
+            <form class="pt-5" on:submit=new_alias>
+                <h2 class="text-center font-bold leading-12">Aliases</h2>
+                <ul>
+                    {move || {
+                        aliases
+                            .get()
+                            .into_iter()
+                            .map(|a| {
+                                view! {
+                                    <li class="bg-zinc-700 w-full flex justify-between items-center leading-12 rounded-[3rem] pl-12 pr-12 mb-3">
+                                        <p>{format!("{} ({:?})", a.pattern, a.kind)}</p>
+                                        <p>{a.project.clone()}</p>
+                                        <button
+                                            class="hover:text-red-400"
+                                            type="button"
+                                            on:click=move |_| {
+                                                aliases.update(|list| list.retain(|l| l.id != a.id));
+                                                spawn_local(async move {
+                                                    let _ = remove_alias(a.id).await;
+                                                });
+                                            }
+                                        >
+                                            "Remove"
+                                        </button>
+                                    </li>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </ul>
+                <div class="grid grid-cols-[repeat(3,33%)]">
+                    <select class="h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="alias_project" prop:value=move || alias_project.get() on:change=move |ev| alias_project.set(event_target_value(&ev))>
+                        {move || {
+                            tracked
+                                .get()
+                                .into_iter()
+                                .map(|p| view! { <option value=p.clone()>{p.clone()}</option> })
+                                .collect_view()
+                        }}
+                    </select>
+                    <select class="h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="alias_kind" prop:value=move || alias_kind.get() on:change=move |ev| alias_kind.set(event_target_value(&ev))>
+                        <option value="0">"Exact"</option>
+                        <option value="1">"Glob"</option>
+                        <option value="2">"Regex"</option>
+                    </select>
+                    <input class="h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="alias_pattern" placeholder="Hackatime name" bind:value=alias_pattern />
+                </div>
+                <p class="text-center text-red-400">{alias_error}</p>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Add alias" />
+            </form>
         </div>
     }
 }
+
+#[component]
+fn WindowOptions() -> impl IntoView {
+    view! {
+        <option value="0">"Since last"</option>
+        <option value="1">"Since midnight"</option>
+        <option value="2">"Last 24h"</option>
+        <option value="3">"This week"</option>
+    }
+}
@@ -0,0 +1,164 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveTime, Timelike};
+use leptos::{ev::SubmitEvent, prelude::*, task::spawn_local};
+
+use crate::{
+    datatypes::JournalEntry, export_journal, query_journal, save_journal_entry, set_journal_prompt,
+};
+
+#[component]
+pub fn JournalPage() -> impl IntoView {
+    let today = Local::now().date_naive();
+    let day = RwSignal::new(today.to_string());
+    let entries = RwSignal::new(Vec::<JournalEntry>::new());
+    let prompt = RwSignal::new("".to_string());
+    let week = RwSignal::new(format!("{}-W{:02}", today.iso_week().year(), today.iso_week().week()));
+    let (export, set_export) = signal("".to_string());
+    let (error, set_error) = signal("".to_string());
+
+    let journal_loader = Resource::new(
+        move || day.get().parse::<NaiveDate>().ok(),
+        |day| async move {
+            match day {
+                Some(day) => query_journal(day).await.map(Some),
+                None => Ok(None),
+            }
+        },
+    );
+
+    Effect::new(move || match journal_loader.get() {
+        Some(Ok(Some(j))) => {
+            entries.set(j.entries);
+            prompt.set(
+                j.prompt
+                    .map(|p| format!("{:02}:{:02}", p / 60, p % 60))
+                    .unwrap_or_default(),
+            );
+            set_error.set("".to_string());
+        }
+        Some(Err(ServerFnError::ServerError(e))) => set_error.set(e),
+        Some(Err(e)) => set_error.set(e.to_string()),
+        _ => (),
+    });
+
+    let save_entry = move |i: usize| {
+        let entry = match entries.get().get(i) {
+            Some(e) => e.clone(),
+            None => return,
+        };
+        spawn_local(async move {
+            match save_journal_entry(entry).await {
+                Ok(_) => set_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let save_prompt = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        // an empty time turns the prompt off
+        let minute = match prompt.get().as_str() {
+            "" => None,
+            p => match NaiveTime::parse_from_str(p, "%H:%M") {
+                Ok(t) => Some((t.hour() * 60 + t.minute()) as i32),
+                Err(_) => return,
+            },
+        };
+        spawn_local(async move {
+            match set_journal_prompt(minute).await {
+                Ok(_) => set_error.set("".to_string()),
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    let export_week = move |ev: SubmitEvent| {
+        ev.prevent_default();
+        let monday = match NaiveDate::parse_from_str(&format!("{}-1", week.get()), "%G-W%V-%u") {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+        spawn_local(async move {
+            match export_journal(monday).await {
+                Ok(m) => set_export.set(m),
+                Err(ServerFnError::ServerError(e)) => set_error.set(e),
+                Err(e) => set_error.set(e.to_string()),
+            }
+        });
+    };
+
+    view! {
+        <div class="col-start-1 row-start-1 justify-self-center pt-5">
+            <div class="grid grid-cols-[25%_75%]">
+                <p class="col-start-1 h-12 leading-12">Day:</p>
+                <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="day" type="date" bind:value=day />
+            </div>
+            <form class="pt-5" on:submit=save_prompt>
+                <div class="grid grid-cols-[25%_75%]">
+                    <p class="col-start-1 h-12 leading-12">Prompt at:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="prompt" type="time" bind:value=prompt />
+                </div>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Save prompt" />
+            </form>
+            <p class="text-center pt-3">"Leave the time empty to not get a notification in the evening."</p>
+            <a class="underline block text-center pt-3" href="/">"Back"</a>
+        </div>
+        <div class="col-start-2 row-start-1 justify-self-center pt-5 w-full px-12">
+            <Suspense fallback=move || view! { <p class="text-center">"Loading..."</p> }>
+                {move || {
+                    journal_loader.track();
+                    entries
+                        .get()
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, e)| {
+                            view! {
+                                <h2 class="text-center font-bold leading-12">{e.project}</h2>
+                                <textarea
+                                    class="w-full h-48 bg-zinc-700 rounded-[1.5rem] p-5 focus:outline-none"
+                                    prop:value=e.text
+                                    on:input=move |ev| {
+                                        let text = event_target_value(&ev);
+                                        entries.update(|list| list[i].text = text);
+                                    }
+                                />
+                                <button class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" on:click=move |_| save_entry(i)>
+                                    "Save"
+                                </button>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </Suspense>
+            <p class="text-center text-red-400 pt-3">{error}</p>
+        </div>
+        <div class="col-start-3 row-start-1 justify-self-center pt-5 w-full pr-12">
+            <form on:submit=export_week>
+                <div class="grid grid-cols-[25%_75%]">
+                    <p class="col-start-1 h-12 leading-12">Week:</p>
+                    <input class="col-start-2 h-[calc(3rem-.5rem)] leading-[calc(3rem-.5rem)] bg-zinc-700 text-center rounded-[calc(3rem-.5rem)] focus:outline-none m-[0.25rem]" name="week" type="week" bind:value=week />
+                </div>
+                <input class="bg-zinc-700 text-center h12 leading-12 w-full rounded-[3rem] hover:bg-zinc-600 mt-3" type="submit" value="Export as Markdown" />
+            </form>
+            <Show when=move || !export.get().is_empty()>
+                <textarea class="w-full h-96 bg-zinc-700 rounded-[1.5rem] p-5 mt-3 focus:outline-none" readonly prop:value=export />
+                <a class="underline block text-center pt-3" href=move || data_url(&export.get()) download=move || format!("devlog-{}.md", week.get())>
+                    "Download"
+                </a>
+            </Show>
+        </div>
+    }
+}
+
+/// Markdown as a `data:` URL so it can be downloaded without another request.
+fn data_url(markdown: &str) -> String {
+    let mut out = "data:text/markdown;charset=utf-8,".to_string();
+    for b in markdown.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
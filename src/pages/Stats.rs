@@ -0,0 +1,170 @@
+use leptos::prelude::*;
+
+use crate::{
+    datatypes::{Bar, GoalStatus},
+    query_stats,
+};
+
+const HEIGHT: f64 = 160.0;
+const BAR_WIDTH: f64 = 40.0;
+
+#[component]
+pub fn StatsPage() -> impl IntoView {
+    let stats_loader = Resource::new(|| (), |_| query_stats());
+
+    view! {
+        <Suspense fallback=move || {
+            view! { <p>"Loading..."</p> }
+        }>
+            {move || {
+                stats_loader
+                    .get()
+                    .map(|res| match res {
+                        Ok(stats) => {
+                            view! {
+                                <div class="col-start-1 row-start-1 justify-self-center pt-5 w-full px-12">
+                                    <h2 class="text-center font-bold leading-12">This week</h2>
+                                    <BarChart bars=stats.days />
+                                    <p class="text-center pt-3">"Hours on your projects, the yellow lines are your goals."</p>
+                                    <a class="underline block text-center pt-3" href="/">"Back"</a>
+                                </div>
+                                <div class="col-start-2 row-start-1 justify-self-center pt-5 w-full px-12">
+                                    <h2 class="text-center font-bold leading-12">Every week</h2>
+                                    <BarChart bars=stats.weeks />
+                                    <p class="text-center pt-3">"Hours on your projects, the yellow lines are your weekly goal."</p>
+                                    {stats
+                                        .goal
+                                        .map(|status| {
+                                            view! {
+                                                <h2 class="text-center font-bold leading-12 pt-5">Event goal</h2>
+                                                <p class="text-center pb-3">
+                                                    {format!(
+                                                        "{:.1}h of {}h on {}, {:.1}h a week needed until {}.",
+                                                        status.done as f64 / 3600.0,
+                                                        status.goal.hours,
+                                                        status.goal.project,
+                                                        status.required as f64 / 3600.0,
+                                                        status.goal.end.format("%d.%m.%Y"),
+                                                    )}
+                                                </p>
+                                                <Burndown status />
+                                                <p class="text-center pt-3">"Hours left, the yellow line is working on it evenly."</p>
+                                            }
+                                        })}
+                                </div>
+                                <div class="col-start-3 row-start-1 justify-self-center pt-5 w-full px-12">
+                                    <h2 class="text-center font-bold leading-12">Projects this week</h2>
+                                    <ProjectChart bars=stats.projects />
+                                </div>
+                            }
+                                .into_any()
+                        }
+                        Err(ServerFnError::ServerError(e)) => {
+                            view! { <p class="col-start-2 text-center pt-5 text-red-400">{e}</p> }.into_any()
+                        }
+                        Err(e) => {
+                            view! { <p class="col-start-2 text-center pt-5 text-red-400">{e.to_string()}</p> }.into_any()
+                        }
+                    })
+            }}
+        </Suspense>
+    }
+}
+
+/// Vertical bars with a goal line each, scaled to the biggest value.
+#[component]
+fn BarChart(bars: Vec<Bar>) -> impl IntoView {
+    let max = bars
+        .iter()
+        .map(|b| b.seconds.max(b.goal.unwrap_or(0)))
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let scale = move |sec: i64| sec as f64 / max * HEIGHT;
+    let width = (bars.len() as f64 * BAR_WIDTH).max(BAR_WIDTH);
+
+    view! {
+        <svg class="w-full" viewBox=format!("0 0 {} {}", width, HEIGHT + 20.0)>
+            {bars
+                .into_iter()
+                .enumerate()
+                .map(|(i, bar)| {
+                    let x = i as f64 * BAR_WIDTH;
+                    let height = scale(bar.seconds);
+                    view! {
+                        <g>
+                            <rect class="fill-sky-600" x=x + 4.0 y=HEIGHT - height width=BAR_WIDTH - 8.0 height=height>
+                                <title>{format!("{}: {:.1}h", bar.label, bar.seconds as f64 / 3600.0)}</title>
+                            </rect>
+                            {bar
+                                .goal
+                                .map(|goal| {
+                                    let y = HEIGHT - scale(goal);
+                                    view! { <line class="stroke-yellow-400" stroke-width="2" x1=x + 2.0 x2=x + BAR_WIDTH - 2.0 y1=y y2=y /> }
+                                })}
+                            <text class="fill-zinc-200 text-[8px]" x=x + BAR_WIDTH / 2.0 y=HEIGHT + 14.0 text-anchor="middle">
+                                {bar.label}
+                            </text>
+                        </g>
+                    }
+                })
+                .collect_view()}
+        </svg>
+    }
+}
+
+/// Hours left of the long-term goal over the event.
+#[component]
+fn Burndown(status: GoalStatus) -> impl IntoView {
+    let days = ((status.goal.end - status.goal.start).num_days() + 1).max(1) as f64;
+    let total = (status.goal.hours as i64 * 60 * 60).max(1) as f64;
+    let width = 300.0;
+    let x = move |day: f64| day / days * width;
+    let y = move |left: i64| HEIGHT - left.max(0) as f64 / total * HEIGHT;
+    let points = std::iter::once(format!("0,{}", y(total as i64)))
+        .chain(status.remaining.iter().map(|(date, left)| {
+            format!("{},{}", x(((*date - status.goal.start).num_days() + 1) as f64), y(*left))
+        }))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    view! {
+        <svg class="w-full" viewBox=format!("0 0 {} {}", width, HEIGHT + 20.0)>
+            <line class="stroke-yellow-400" stroke-width="2" x1="0" y1=y(total as i64) x2=width y2=HEIGHT />
+            <polyline class="stroke-sky-500" fill="none" stroke-width="2" points=points />
+            <text class="fill-zinc-200 text-[8px]" x="0" y=HEIGHT + 14.0>{status.goal.start.format("%d.%m").to_string()}</text>
+            <text class="fill-zinc-200 text-[8px]" x=width y=HEIGHT + 14.0 text-anchor="end">{status.goal.end.format("%d.%m").to_string()}</text>
+        </svg>
+    }
+}
+
+/// Horizontal bars so long project names fit.
+#[component]
+fn ProjectChart(bars: Vec<Bar>) -> impl IntoView {
+    let max = bars.iter().map(|b| b.seconds).max().unwrap_or(0).max(1) as f64;
+    let row = 24.0;
+    let height = (bars.len() as f64 * row).max(row);
+
+    view! {
+        <svg class="w-full" viewBox=format!("0 0 300 {}", height)>
+            {bars
+                .into_iter()
+                .enumerate()
+                .map(|(i, bar)| {
+                    let y = i as f64 * row;
+                    view! {
+                        <g>
+                            <rect class="fill-sky-600" x="120" y=y + 4.0 width=bar.seconds as f64 / max * 140.0 height=row - 8.0 />
+                            <text class="fill-zinc-200 text-[10px]" x="115" y=y + row / 2.0 + 3.0 text-anchor="end">
+                                {bar.label}
+                            </text>
+                            <text class="fill-zinc-200 text-[10px]" x="265" y=y + row / 2.0 + 3.0>
+                                {format!("{:.1}h", bar.seconds as f64 / 3600.0)}
+                            </text>
+                        </g>
+                    }
+                })
+                .collect_view()}
+        </svg>
+    }
+}
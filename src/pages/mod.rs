@@ -1,4 +1,12 @@
 #![allow(non_snake_case)]
+mod Admin;
+mod Calendar;
 mod Home;
+mod Journal;
+mod Stats;
 
+pub use Admin::*;
+pub use Calendar::*;
 pub use Home::*;
+pub use Journal::*;
+pub use Stats::*;
@@ -0,0 +1,230 @@
+#![cfg(feature = "ssr")]
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::datatypes::{Alias, AliasKind};
+
+/// Checks if an alias rule applies to a Hackatime project name.
+pub fn matches(alias: &Alias, name: &str) -> bool {
+    match alias.kind {
+        AliasKind::Exact => alias.pattern == name,
+        AliasKind::Glob => match glob_to_regex(&alias.pattern) {
+            Ok(r) => r.is_match(name),
+            Err(_) => false,
+        },
+        AliasKind::Regex => match Regex::new(&alias.pattern) {
+            Ok(r) => r.is_match(name),
+            Err(_) => false,
+        },
+    }
+}
+
+/// Whether a tracked project name looks like a glob or regex, those belong in aliases.
+pub fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?', '[', ']', '^', '$', '|', '\\'])
+}
+
+pub fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!(
+        "^{}$",
+        regex::escape(glob).replace(r"\*", ".*").replace(r"\?", ".")
+    ))
+}
+
+/// All projects of a Hackatime stats response with their time in seconds.
+pub fn all_projects(data: &Value) -> Vec<(String, i64)> {
+    totals(data, "projects")
+}
+
+/// The named totals of one feature of a Hackatime stats response, empty if it's missing.
+pub fn totals(data: &Value, feature: &str) -> Vec<(String, i64)> {
+    let mut out = Vec::new();
+    if let Some(items) = data
+        .get("data")
+        .and_then(|d| d.get(feature))
+        .and_then(|p| p.as_array())
+    {
+        for i in items {
+            if let (Some(name), Some(sec)) = (
+                i.get("name").and_then(|n| n.as_str()),
+                i.get("total_seconds").and_then(|s| s.as_i64()),
+            ) {
+                out.push((name.to_string(), sec));
+            }
+        }
+    }
+    out
+}
+
+/// The tracked project a Hackatime project counts toward, exact names win over aliases.
+pub fn resolve<'a>(name: &str, tracked: &'a [String], aliases: &[Alias]) -> Option<&'a String> {
+    tracked.iter().find(|p| *p == name).or_else(|| {
+        aliases
+            .iter()
+            .filter(|a| matches(a, name))
+            .find_map(|a| tracked.iter().find(|p| **p == a.project))
+    })
+}
+
+/// Time spent on each of the tracked projects, projects without time count as 0.
+pub fn get_projects(data: &Value, tracked: &[String], aliases: &[Alias]) -> Vec<(String, i64)> {
+    let mut out: Vec<(String, i64)> = tracked.iter().map(|p| (p.clone(), 0)).collect();
+    for (name, sec) in all_projects(data) {
+        if let Some(project) = resolve(&name, tracked, aliases) {
+            if let Some(p) = out.iter_mut().find(|p| p.0 == *project) {
+                p.1 += sec;
+            }
+        }
+    }
+    out
+}
+
+/// Seconds per day on `project` from stored (project, day, seconds) snapshots.
+pub fn daily_totals(
+    snapshots: &[(String, NaiveDate, i64)],
+    project: &str,
+    aliases: &[Alias],
+) -> Vec<(NaiveDate, i64)> {
+    let tracked = [project.to_string()];
+    let mut out: Vec<(NaiveDate, i64)> = Vec::new();
+    for (name, day, sec) in snapshots {
+        if resolve(name, &tracked, aliases).is_none() {
+            continue;
+        }
+        match out.iter_mut().find(|d| d.0 == *day) {
+            Some(d) => d.1 += sec,
+            None => out.push((*day, *sec)),
+        }
+    }
+    out
+}
+
+/// Projects with time that don't count toward any tracked project.
+pub fn untracked_projects(data: &Value, tracked: &[String], aliases: &[Alias]) -> Vec<(String, i64)> {
+    all_projects(data)
+        .into_iter()
+        .filter(|(name, sec)| *sec > 0 && resolve(name, tracked, aliases).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(project: &str, kind: AliasKind, pattern: &str) -> Alias {
+        Alias {
+            id: 0,
+            project: project.to_string(),
+            kind,
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn globs() {
+        // (glob, name, matches)
+        let cases = [
+            ("siege-*", "siege-reminder", true),
+            ("siege-*", "siege-", true),
+            ("siege-*", "my-siege-reminder", false),
+            ("*-reminder", "siege-reminder", true),
+            ("siege-?", "siege-2", true),
+            ("siege-?", "siege-10", false),
+            ("siege", "siege-reminder", false),
+            // regex characters in globs are literal
+            ("a.b", "axb", false),
+            ("a.b", "a.b", true),
+            ("c++", "c++", true),
+            ("c++", "cc", false),
+            ("(x)|y", "(x)|y", true),
+            ("(x)|y", "y", false),
+            ("[ab]", "a", false),
+            ("[ab]", "[ab]", true),
+            ("^$", "^$", true),
+            ("back\\slash", "back\\slash", true),
+        ];
+        for (glob, name, expected) in cases {
+            let regex = glob_to_regex(glob).unwrap();
+            assert_eq!(regex.is_match(name), expected, "{} on {}", glob, name);
+            assert_eq!(
+                matches(&alias("p", AliasKind::Glob, glob), name),
+                expected,
+                "{} on {}",
+                glob,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn alias_kinds() {
+        // (kind, pattern, name, matches)
+        let cases = [
+            (AliasKind::Exact, "siege", "siege", true),
+            (AliasKind::Exact, "siege", "Siege", false),
+            (AliasKind::Exact, "siege*", "siege-reminder", false),
+            (AliasKind::Glob, "siege*", "siege-reminder", true),
+            (AliasKind::Regex, "^siege(-.*)?$", "siege-reminder", true),
+            (AliasKind::Regex, "^siege(-.*)?$", "siege", true),
+            (AliasKind::Regex, "^siege$", "siege-reminder", false),
+            // unanchored regexes match anywhere
+            (AliasKind::Regex, "remind", "siege-reminder", true),
+            // invalid regexes match nothing
+            (AliasKind::Regex, "(", "(", false),
+            (AliasKind::Regex, "[a-", "a", false),
+            (AliasKind::Regex, "*", "anything", false),
+        ];
+        for (kind, pattern, name, expected) in cases {
+            assert_eq!(
+                matches(&alias("p", kind, pattern), name),
+                expected,
+                "{:?} {} on {}",
+                kind,
+                pattern,
+                name
+            );
+        }
+        assert!(Regex::new("(").is_err());
+    }
+
+    #[test]
+    fn resolves() {
+        let tracked = ["siege".to_string(), "journal".to_string()];
+        let aliases = [
+            alias("siege", AliasKind::Exact, "siege-old"),
+            alias("siege", AliasKind::Glob, "siege-v*"),
+            alias("journal", AliasKind::Regex, "^devlog-[0-9]+$"),
+            // for a project that isn't tracked
+            alias("other", AliasKind::Glob, "*"),
+            alias("siege", AliasKind::Regex, "("),
+        ];
+        // (name, resolves to)
+        let cases = [
+            ("siege", Some("siege")),
+            ("journal", Some("journal")),
+            ("siege-old", Some("siege")),
+            ("siege-v2", Some("siege")),
+            ("devlog-12", Some("journal")),
+            ("devlog-x", None),
+            ("unrelated", None),
+            ("(", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                resolve(name, &tracked, &aliases).map(String::as_str),
+                expected,
+                "{}",
+                name
+            );
+        }
+
+        // exact names win over aliases of another project
+        let aliases = [alias("siege", AliasKind::Glob, "jour*")];
+        assert_eq!(
+            resolve("journal", &tracked, &aliases).map(String::as_str),
+            Some("journal")
+        );
+    }
+}
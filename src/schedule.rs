@@ -0,0 +1,327 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike, Weekday};
+
+use crate::datatypes::{Pace, Settings, Slot, Window};
+
+/// Minutes in a week, slot times wrap around at this value.
+pub const WEEK: i64 = 7 * 24 * 60;
+/// Submissions are due monday 04:00 UTC, that's where a week starts.
+pub const WEEK_START: i64 = 4 * 60;
+
+/// The monday of the week `at` (UTC) belongs to, weeks go from submission to submission.
+pub fn week_of(at: NaiveDateTime) -> NaiveDate {
+    (at - Duration::minutes(WEEK_START))
+        .date()
+        .week(Weekday::Mon)
+        .first_day()
+}
+
+/// Parses durations like `24h, 6h, 1h 30m, 15m` into minutes, `None` if one of them is invalid.
+pub fn parse_offsets(text: &str) -> Option<Vec<i64>> {
+    let mut out = Vec::new();
+    for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let mut minutes = 0;
+        for unit in part.split_whitespace() {
+            let (number, factor) = match unit.char_indices().last()? {
+                (i, 'd') => (&unit[..i], 24 * 60),
+                (i, 'h') => (&unit[..i], 60),
+                (i, 'm') => (&unit[..i], 1),
+                _ => return None,
+            };
+            minutes += number.parse::<i64>().ok()? * factor;
+        }
+        out.push(minutes);
+    }
+    out.sort_unstable_by(|a, b| b.cmp(a));
+    out.dedup();
+    Some(out)
+}
+
+/// The reverse of `parse_offsets`.
+pub fn format_offsets(offsets: &[i64]) -> String {
+    offsets
+        .iter()
+        .map(|o| match (o / 60, o % 60) {
+            (0, m) => format!("{}m", m),
+            (h, 0) => format!("{}h", h),
+            (h, m) => format!("{}h {}m", h, m),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Settings {
+    pub fn slots(&self) -> [Slot; 7] {
+        [
+            self.monday,
+            self.tuesday,
+            self.wednesday,
+            self.thursday,
+            self.friday,
+            self.saturday,
+            self.sunday,
+        ]
+    }
+
+    /// The day (0 is monday) whose reminder is due `minute` minutes after monday 00:00 UTC.
+    pub fn due(&self, minute: i64) -> Option<usize> {
+        self.slots()
+            .iter()
+            .position(|s| s.time.rem_euclid(WEEK) == minute)
+    }
+
+    /// Replaces a days slot, used for one-off overrides.
+    pub fn with_slot(mut self, day: usize, time: i64, goal: i32) -> Settings {
+        let slot = match day {
+            0 => &mut self.monday,
+            1 => &mut self.tuesday,
+            2 => &mut self.wednesday,
+            3 => &mut self.thursday,
+            4 => &mut self.friday,
+            5 => &mut self.saturday,
+            _ => &mut self.sunday,
+        };
+        slot.time = time;
+        slot.goal = goal;
+        self
+    }
+
+    /// The users local date at a UTC time.
+    pub fn local_date(&self, at: NaiveDateTime) -> NaiveDate {
+        (at + Duration::seconds(self.offset as i64)).date()
+    }
+
+    /// Minutes since local midnight at a UTC time.
+    pub fn local_minute(&self, at: NaiveDateTime) -> i64 {
+        let time = (at + Duration::seconds(self.offset as i64)).time();
+        (time.hour() * 60 + time.minute()) as i64
+    }
+
+    /// Goal of a days reminder in hours.
+    pub fn goal(&self, day: usize) -> i64 {
+        let slots = self.slots();
+        match slots[day].window {
+            Window::WeekToDate => slots[..=day].iter().map(|s| s.goal as i64).sum(),
+            _ => slots[day].goal as i64,
+        }
+    }
+
+    /// Where the week ends up if the average pace of the enabled days so far keeps up. `seconds`
+    /// is the time since the week started, `None` before the first or after the last enabled day.
+    pub fn projection(&self, minute: i64, seconds: i64) -> Option<Pace> {
+        let since_start = |time: i64| (time - WEEK_START).rem_euclid(WEEK);
+        let slots = self.slots();
+        let enabled: Vec<&Slot> = slots.iter().filter(|s| s.goal > 0).collect();
+        let passed = enabled
+            .iter()
+            .filter(|s| since_start(s.time) <= since_start(minute))
+            .count() as i64;
+        if passed == 0 || passed == enabled.len() as i64 {
+            return None;
+        }
+        Some(Pace {
+            projected: seconds * enabled.len() as i64 / passed,
+            goal: enabled.iter().map(|s| s.goal as i64).sum::<i64>() * 60 * 60,
+        })
+    }
+
+    /// Start of the window a days reminder measures, in minutes since monday 00:00 UTC.
+    /// Negative if the window starts in the previous week.
+    pub fn window_start(&self, day: usize) -> i64 {
+        let slots = self.slots();
+        let time = slots[day].time.rem_euclid(WEEK);
+        match slots[day].window {
+            Window::PreviousSlot => since(time, slots[(day + 6) % 7].time),
+            Window::LocalMidnight => {
+                let offset = self.offset as i64 / 60;
+                (time + offset).div_euclid(24 * 60) * 24 * 60 - offset
+            }
+            Window::Rolling => time - 24 * 60,
+            Window::WeekToDate => since(time, WEEK_START),
+        }
+    }
+}
+
+impl Pace {
+    pub fn message(&self) -> String {
+        let diff = self.projected - self.goal;
+        let end = match diff {
+            0 => format!("right at {}", hm(self.goal)),
+            d if d < 0 => format!("{} short of {}", hm(-d), hm(self.goal)),
+            d => format!("{} over {}", hm(d), hm(self.goal)),
+        };
+        format!("At this pace you'll finish the week at {}, {}.", hm(self.projected), end)
+    }
+}
+
+pub fn hm(sec: i64) -> String {
+    match (sec / 3600, sec % 3600 / 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// The last occurrence of the weekly `start` before `time`, a full week back if they are equal.
+fn since(time: i64, start: i64) -> i64 {
+    let gap = (time - start).rem_euclid(WEEK);
+    time - if gap == 0 { WEEK } else { gap }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60;
+
+    fn settings(window: Window, offset: i32) -> Settings {
+        let slot = |day: i64| Slot {
+            time: day * DAY + 18 * 60,
+            goal: day as i32 + 1,
+            window,
+        };
+        Settings {
+            monday: slot(0),
+            tuesday: slot(1),
+            wednesday: slot(2),
+            thursday: slot(3),
+            friday: slot(4),
+            saturday: slot(5),
+            sunday: slot(6),
+            offset,
+        }
+    }
+
+    #[test]
+    fn weeks_start_at_the_deadline() {
+        let monday = NaiveDate::from_ymd_opt(2025, 10, 6).unwrap();
+        let at = |d: u32, h: u32| NaiveDate::from_ymd_opt(2025, 10, d).unwrap().and_hms_opt(h, 0, 0).unwrap();
+        assert_eq!(week_of(at(6, 3)), monday - Duration::days(7));
+        assert_eq!(week_of(at(6, 4)), monday);
+        assert_eq!(week_of(at(12, 23)), monday);
+        assert_eq!(week_of(at(13, 3)), monday);
+    }
+
+    #[test]
+    fn pace_uses_the_enabled_days_so_far() {
+        let mut s = settings(Window::PreviousSlot, 0);
+        s.saturday.goal = 0;
+        // goals 1 + 2 + 3 + 4 + 5 + 7 on six enabled days, three passed by wednesday evening
+        let pace = s.projection(2 * DAY + 19 * 60, 5 * 60 * 60).unwrap();
+        assert_eq!(pace, Pace { projected: 10 * 60 * 60, goal: 22 * 60 * 60 });
+        assert_eq!(
+            pace.message(),
+            "At this pace you'll finish the week at 10h, 12h short of 22h."
+        );
+        assert_eq!(
+            Pace { projected: 11 * 3600 + 20 * 60, goal: 11 * 3600 }.message(),
+            "At this pace you'll finish the week at 11h 20m, 20m over 11h."
+        );
+    }
+
+    #[test]
+    fn no_pace_without_days_left() {
+        let s = settings(Window::PreviousSlot, 0);
+        // monday's reminder didn't happen yet
+        assert_eq!(s.projection(WEEK_START, 60), None);
+        assert_eq!(s.projection(6 * DAY + 19 * 60, 60), None);
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offsets("24h, 6h,1h 30m, 15m"), Some(vec![1440, 360, 90, 15]));
+        assert_eq!(parse_offsets("15m, 1d, 15m"), Some(vec![1440, 15]));
+        assert_eq!(parse_offsets(""), Some(vec![]));
+        assert_eq!(parse_offsets("6 hours"), None);
+        assert_eq!(format_offsets(&[1440, 90, 15]), "24h, 1h 30m, 15m");
+    }
+
+    #[test]
+    fn due_every_weekday() {
+        let s = settings(Window::PreviousSlot, 0);
+        for day in 0..7 {
+            assert_eq!(s.due(day as i64 * DAY + 18 * 60), Some(day));
+            assert_eq!(s.due(day as i64 * DAY + 17 * 60), None);
+        }
+    }
+
+    #[test]
+    fn due_wraps_into_previous_week() {
+        let mut s = settings(Window::PreviousSlot, 2 * 60 * 60);
+        // monday 01:00 at UTC+2 is sunday 23:00 UTC
+        s.monday.time = -60;
+        assert_eq!(s.due(WEEK - 60), Some(0));
+    }
+
+    #[test]
+    fn previous_slot_every_weekday() {
+        let s = settings(Window::PreviousSlot, 0);
+        for day in 0..7 {
+            let time = day as i64 * DAY + 18 * 60;
+            assert_eq!(s.window_start(day), time - DAY);
+        }
+    }
+
+    #[test]
+    fn previous_slot_uses_the_previous_days_time() {
+        let mut s = settings(Window::PreviousSlot, 0);
+        s.tuesday.time = DAY + 9 * 60;
+        assert_eq!(s.window_start(1), 18 * 60);
+        assert_eq!(s.window_start(2), DAY + 9 * 60);
+    }
+
+    #[test]
+    fn local_midnight_every_weekday() {
+        let s = settings(Window::LocalMidnight, 2 * 60 * 60);
+        for day in 0..7 {
+            assert_eq!(s.window_start(day), day as i64 * DAY - 2 * 60);
+        }
+        let s = settings(Window::LocalMidnight, -8 * 60 * 60);
+        for day in 0..7 {
+            // 18:00 UTC is 10:00 at UTC-8, local midnight was 08:00 UTC
+            assert_eq!(s.window_start(day), day as i64 * DAY + 8 * 60);
+        }
+    }
+
+    #[test]
+    fn rolling_every_weekday() {
+        let s = settings(Window::Rolling, 0);
+        for day in 0..7 {
+            assert_eq!(s.window_start(day), day as i64 * DAY + 18 * 60 - DAY);
+        }
+    }
+
+    #[test]
+    fn week_to_date_every_weekday() {
+        let s = settings(Window::WeekToDate, 0);
+        for day in 0..7 {
+            assert_eq!(s.window_start(day), WEEK_START);
+            assert_eq!(s.goal(day), (1..=day as i64 + 1).sum::<i64>());
+        }
+    }
+
+    #[test]
+    fn local_date_and_minute() {
+        let s = settings(Window::PreviousSlot, 2 * 60 * 60);
+        let at = NaiveDate::from_ymd_opt(2025, 9, 7)
+            .unwrap()
+            .and_hms_opt(23, 30, 0)
+            .unwrap();
+        assert_eq!(s.local_date(at), NaiveDate::from_ymd_opt(2025, 9, 8).unwrap());
+        assert_eq!(s.local_minute(at), 90);
+    }
+
+    #[test]
+    fn override_replaces_a_slot() {
+        let s = settings(Window::PreviousSlot, 0).with_slot(2, 2 * DAY + 9 * 60, 5);
+        assert_eq!(s.due(2 * DAY + 9 * 60), Some(2));
+        assert_eq!(s.goal(2), 5);
+        assert_eq!(s.window_start(3), 2 * DAY + 9 * 60);
+    }
+
+    #[test]
+    fn week_to_date_before_the_deadline() {
+        let mut s = settings(Window::WeekToDate, 0);
+        s.monday.time = 60;
+        assert_eq!(s.window_start(0), WEEK_START - WEEK);
+    }
+}
@@ -1,3 +1,18 @@
 mod server;
 
-pub use server::{query_data, set_project, set_times};
+pub use server::{
+    add_alias, add_calendar, add_checklist_item, add_milestone, add_vacation, confirm_link,
+    create_api_token, export_journal, list_api_tokens, list_sessions, logout, oidc_enabled,
+    pending_link, query_admin, query_admin_user, query_calendar, query_data, query_journal,
+    query_stats, query_week, remove_alias, remove_calendar, remove_checklist_item,
+    remove_milestone, remove_override, remove_vacation, request_code, revoke_api_token,
+    revoke_session, save_journal_entry,
+    set_carry, set_channels, set_checklist_item_done, set_deadline_alerts, set_journal_prompt,
+    set_long_goal, set_milestone_done, set_override, set_project_carry, set_projects,
+    set_shipped, set_times, set_user_disabled, verify_code,
+};
+#[cfg(feature = "ssr")]
+pub(crate) use server::{
+    progress_for, projects_for, save_channels, save_long_goal, save_projects, save_settings,
+    week_for,
+};
@@ -1,20 +1,1128 @@
-use chrono::{Datelike, Duration, Utc};
+use chrono::NaiveDate;
 use leptos::{prelude::ServerFnError, server};
-use reqwest::{Client, StatusCode};
+
+use crate::datatypes::{
+    AdminData, AdminUser, Alias, AliasKind, ApiScope, ApiToken, CalendarData, CalendarFile,
+    CarryMode, Channels, ChecklistItem, Data, Journal, JournalEntry, LongGoal, Milestone, Override,
+    ProjectCarry, SessionInfo, Settings, Stats, Submission, Vacation, WeekData,
+};
+
+#[cfg(feature = "ssr")]
+use crate::{
+    auth::{
+        cookie, current_admin, current_user, end_session, hash_token, new_code, require_admin,
+        require_same_origin, require_user, session, set_cookie, start_session, topic_error,
+        CODE_ATTEMPTS, CODE_MINUTES, CODE_REQUESTS, CODE_WINDOW_MINUTES,
+    },
+    datatypes::{
+        Bar, Carry, DayResult, Evaluation, Event, EventDay, Progress, Project, Projects, Role,
+        Slot, Window,
+    },
+    db::{
+        aliases, calendars, carry_from_row, checklist, create_checklists, create_user,
+        deadline_alerts_from_row, disabled, expand_calendars, has_secret_topic, journal,
+        long_goal_from_row, milestones, new_token, overrides, project_carry_from_row,
+        save_occurrences, save_submission, save_week_projects, set_disabled, set_ntfy_topic,
+        settings_from_row, shipped, snapshots, submission, tracked_projects, vacations,
+        week_projects, POOL,
+    },
+    hackatime::{self, stats},
+    ical,
+    journal::{fetch_draft, markdown},
+    notifications::{send, send_to, LAST_RUN},
+    oidc,
+    projects::{all_projects, daily_totals, glob_to_regex, resolve},
+    schedule::{week_of, WEEK, WEEK_START},
+};
+#[cfg(feature = "ssr")]
+use axum::http::HeaderMap;
+#[cfg(feature = "ssr")]
+use chrono::{Datelike, Duration, NaiveDateTime, Utc, Weekday};
+#[cfg(feature = "ssr")]
+use leptos_axum::extract;
+#[cfg(feature = "ssr")]
 use serde_json::Value;
+#[cfg(feature = "ssr")]
+use sqlx::{query, sqlite::SqliteRow, Row};
+
+/// Whether there's a single sign-on button to show.
+#[server]
+pub async fn oidc_enabled() -> Result<bool, ServerFnError> {
+    Ok(oidc::enabled())
+}
+
+/// The single sign-on login waiting to be linked to this account, by email or provider.
+#[server]
+pub async fn pending_link() -> Result<Option<String>, ServerFnError> {
+    let username = current_user().await?;
+    let headers: HeaderMap = extract().await?;
+    match oidc::pending_link(&headers, &username).await {
+        Ok(Some(link)) if link.email.is_empty() => Ok(Some(link.issuer)),
+        Ok(link) => Ok(link.map(|l| l.email)),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Links the waiting single sign-on login to this account if `confirm`, else drops it.
+#[server]
+pub async fn confirm_link(confirm: bool) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    let headers: HeaderMap = extract().await?;
+    let finished = oidc::finish_link(&headers, &username, confirm).await;
+    set_cookie(&cookie(oidc::LINK_COOKIE, "", 0));
+    match finished {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ServerFnError::new(
+            "This login expired, use single sign-on again",
+        )),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Sends a login code to the secret ntfy topic of the account. A new account picks its `topic`
+/// here and needs a Hackatime user of that name, accounts still on the public default topic (their
+/// username) can't get codes because anyone could read them.
+#[server]
+pub async fn request_code(username: String, topic: String) -> Result<(), ServerFnError> {
+    require_same_origin().await?;
+    if username.is_empty() {
+        return Err(ServerFnError::new("Enter your username first"));
+    }
+    let exists = match query("SELECT 1 FROM users WHERE username = ?")
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(r) => r.is_some(),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    // existing accounts keep their topic, `topic` only matters for new ones
+    let topic = if exists {
+        match (disabled(&username).await, has_secret_topic(&username).await) {
+            (Ok(true), _) => return Err(ServerFnError::new("This account is disabled")),
+            (Ok(false), Ok(true)) => String::new(),
+            (Ok(false), Ok(false)) => {
+                return Err(ServerFnError::new(
+                    "This account has no secret ntfy topic, log in with single sign-on or ask an admin to set one",
+                ))
+            }
+            _ => return Err(ServerFnError::new("Database error")),
+        }
+    } else {
+        let topic = topic.trim().to_string();
+        if let Some(e) = topic_error(&username, &topic) {
+            return Err(ServerFnError::new(e));
+        }
+        match hackatime::exists(&username).await {
+            Ok(true) => topic,
+            Ok(false) => return Err(ServerFnError::new("There's no Hackatime user of that name")),
+            Err(e) => return Err(ServerFnError::new(e)),
+        }
+    };
+    let now = Utc::now().naive_utc();
+    let row = match query("SELECT * FROM codes WHERE username = ?")
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(r) => r,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let time = |r: &SqliteRow, column: &str| {
+        NaiveDateTime::parse_from_str(&r.get::<String, _>(column), "%Y-%m-%d %H:%M:%S%.f").ok()
+    };
+    // so nobody can flood someones notifications
+    if row
+        .as_ref()
+        .and_then(|r| time(r, "sent"))
+        .is_some_and(|s| now - s < Duration::minutes(1))
+    {
+        return Err(ServerFnError::new(
+            "Wait a minute before asking for another code",
+        ));
+    }
+    // new codes keep the requests and wrong guesses of the window, so they don't give more tries
+    let (window_start, requests, attempts) = match &row {
+        Some(r)
+            if time(r, "window_start")
+                .is_some_and(|w| now - w < Duration::minutes(CODE_WINDOW_MINUTES)) =>
+        {
+            (
+                r.get::<String, _>("window_start"),
+                r.get::<i64, _>("requests"),
+                r.get::<i64, _>("attempts"),
+            )
+        }
+        _ => (now.to_string(), 0, 0),
+    };
+    if requests >= CODE_REQUESTS || attempts >= CODE_ATTEMPTS {
+        return Err(ServerFnError::new(
+            "Too many codes for this account, try again in an hour",
+        ));
+    }
+    let code = new_code();
+    if query("INSERT OR REPLACE INTO codes (username, code, sent, attempts, window_start, requests, topic) VALUES (?, ?, ?, ?, ?, ?, ?)")
+        .bind(username.clone())
+        .bind(code.clone())
+        .bind(now.to_string())
+        .bind(attempts)
+        .bind(window_start)
+        .bind(requests + 1)
+        .bind(topic.clone())
+        .execute(&*POOL)
+        .await
+        .is_err()
+    {
+        return Err(ServerFnError::new("Database error"));
+    }
+    let body = format!(
+        "{} is your code, it's valid for {} minutes. Ignore this if it wasn't you.",
+        code, CODE_MINUTES
+    );
+    if topic.is_empty() {
+        send(&username, "Your login code", Some("key"), &body).await;
+    } else {
+        send_to(&username, &topic, "Your login code", Some("key"), &body).await;
+    }
+    Ok(())
+}
+
+/// Checks a login code and starts a session, a new username gets an account with the topic it
+/// picked.
+#[server]
+pub async fn verify_code(username: String, code: String) -> Result<(), ServerFnError> {
+    require_same_origin().await?;
+    let row = match query("SELECT * FROM codes WHERE username = ?")
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(ServerFnError::new("Ask for a code first")),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    // the row stays until a login works so its rate limit window does too
+    if row.get::<i64, _>("attempts") >= CODE_ATTEMPTS {
+        return Err(ServerFnError::new(
+            "Too many wrong codes, try again in an hour",
+        ));
+    }
+    let now = Utc::now().naive_utc();
+    let sent: String = row.get("sent");
+    let expired = match NaiveDateTime::parse_from_str(&sent, "%Y-%m-%d %H:%M:%S%.f") {
+        Ok(sent) => now - sent > Duration::minutes(CODE_MINUTES),
+        Err(_) => true,
+    };
+    if expired {
+        return Err(ServerFnError::new("The code expired, ask for a new one"));
+    }
+    if row.get::<String, _>("code") != code.trim() {
+        let _ = query("UPDATE codes SET attempts = attempts + 1 WHERE username = ?")
+            .bind(username)
+            .execute(&*POOL)
+            .await;
+        return Err(ServerFnError::new("Wrong code"));
+    }
+
+    let topic: String = row.get("topic");
+    if query("DELETE FROM codes WHERE username = ?")
+        .bind(username.clone())
+        .execute(&*POOL)
+        .await
+        .is_err()
+    {
+        return Err(ServerFnError::new("Database error"));
+    }
+    if !topic.is_empty() {
+        // someone else may have made the account since, then the code wasn't for it
+        if create_user(&username).await.is_err() {
+            return Err(ServerFnError::new("Database error"));
+        }
+        match query("UPDATE users SET ntfy_topic = ? WHERE username = ? AND ntfy_topic IN ('', ?)")
+            .bind(topic.clone())
+            .bind(username.clone())
+            .bind(topic)
+            .execute(&*POOL)
+            .await
+        {
+            Ok(r) if r.rows_affected() > 0 => (),
+            Ok(_) => return Err(ServerFnError::new("This account exists now, ask for a new code")),
+            Err(_) => return Err(ServerFnError::new("Database error")),
+        }
+    }
+    if start_session(&username).await.is_err() {
+        return Err(ServerFnError::new("Database error"));
+    }
+    Ok(())
+}
+
+#[server]
+pub async fn logout() -> Result<(), ServerFnError> {
+    require_same_origin().await?;
+    end_session().await
+}
+
+/// Every browser the user is logged in with.
+#[server]
+pub async fn list_sessions() -> Result<Vec<SessionInfo>, ServerFnError> {
+    let username = current_user().await?;
+    let current = session().await?.map(|s| s.0).unwrap_or_default();
+    let rows =
+        match query("SELECT rowid, * FROM sessions WHERE username = ? ORDER BY last_used DESC")
+            .bind(username)
+            .fetch_all(&*POOL)
+            .await
+        {
+            Ok(r) => r,
+            Err(_) => return Err(ServerFnError::new("Database error")),
+        };
+    let time = |r: &SqliteRow, column: &str| {
+        NaiveDateTime::parse_from_str(&r.get::<String, _>(column), "%Y-%m-%d %H:%M:%S%.f")
+    };
+    rows.iter()
+        .map(|r| match (time(r, "created"), time(r, "last_used")) {
+            (Ok(created), Ok(last_used)) => Ok(SessionInfo {
+                id: r.get("rowid"),
+                created,
+                last_used,
+                current: r.get::<String, _>("token") == current,
+            }),
+            _ => Err(ServerFnError::new("Database error")),
+        })
+        .collect()
+}
+
+/// The users API tokens, without their secrets.
+#[server]
+pub async fn list_api_tokens() -> Result<Vec<ApiToken>, ServerFnError> {
+    let username = current_user().await?;
+    let rows = match query("SELECT * FROM api_tokens WHERE username = ? ORDER BY id")
+        .bind(username)
+        .fetch_all(&*POOL)
+        .await
+    {
+        Ok(r) => r,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let time = |t: String| NaiveDateTime::parse_from_str(&t, "%Y-%m-%d %H:%M:%S%.f");
+    rows.iter()
+        .map(|r| {
+            let last_used = match r.get::<Option<String>, _>("last_used").map(time) {
+                Some(Ok(t)) => Some(t),
+                Some(Err(_)) => return Err(ServerFnError::new("Database error")),
+                None => None,
+            };
+            match time(r.get("created")) {
+                Ok(created) => Ok(ApiToken {
+                    id: r.get("id"),
+                    name: r.get("name"),
+                    scope: ApiScope::from_i64(r.get("scope")),
+                    created,
+                    last_used,
+                }),
+                Err(_) => Err(ServerFnError::new("Database error")),
+            }
+        })
+        .collect()
+}
+
+/// Creates an API token and returns its secret, which can't be shown again.
+#[server]
+pub async fn create_api_token(name: String, scope: ApiScope) -> Result<String, ServerFnError> {
+    let username = require_user().await?;
+    if name.trim().is_empty() {
+        return Err(ServerFnError::new("Name the token"));
+    }
+    let secret = format!("sr_{}", new_token());
+    match query("INSERT INTO api_tokens (username, name, hash, scope, created) VALUES (?, ?, ?, ?, ?)")
+        .bind(username)
+        .bind(name.trim())
+        .bind(hash_token(&secret))
+        .bind(scope.to_i64())
+        .bind(Utc::now().naive_utc().to_string())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(secret),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn revoke_api_token(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM api_tokens WHERE id = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Logs out another browser.
+#[server]
+pub async fn revoke_session(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM sessions WHERE rowid = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// User counts, how reminders and requests went in the last two weeks and the schedulers lag.
+#[server]
+pub async fn query_admin() -> Result<AdminData, ServerFnError> {
+    current_admin().await?;
+    let now = Utc::now().naive_utc();
+    let counts = match query(
+        "SELECT COUNT(*) AS users, COALESCE(SUM(disabled != 0), 0) AS disabled,
+        (SELECT COUNT(DISTINCT username) FROM evaluations WHERE time >= ?) AS active FROM users",
+    )
+    .bind((now - Duration::days(7)).to_string())
+    .fetch_one(&*POOL)
+    .await
+    {
+        Ok(r) => r,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let first = now.date() - Duration::days(13);
+    let rows = match query(
+        "SELECT substr(time, 1, 10) AS day, kind, SUM(ok) AS ok, COUNT(*) - SUM(ok) AS failed
+        FROM events WHERE time >= ? GROUP BY day, kind",
+    )
+    .bind(first.to_string())
+    .fetch_all(&*POOL)
+    .await
+    {
+        Ok(r) => r,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let mut days: Vec<EventDay> = (0..14)
+        .map(|i| EventDay {
+            day: first + Duration::days(i),
+            ..Default::default()
+        })
+        .collect();
+    for r in rows {
+        let day = match r.get::<String, _>("day").parse::<NaiveDate>() {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+        let Some(d) = days.iter_mut().find(|d| d.day == day) else {
+            continue;
+        };
+        let outcome = (r.get("ok"), r.get("failed"));
+        match r.get::<String, _>("kind").as_str() {
+            "reminder" => d.reminders = outcome,
+            "ntfy" => d.ntfy = outcome,
+            "hackatime" => d.hackatime = outcome,
+            _ => (),
+        }
+    }
+    Ok(AdminData {
+        users: counts.get("users"),
+        disabled: counts.get("disabled"),
+        active: counts.get("active"),
+        days,
+        scheduler: *LAST_RUN.lock().unwrap(),
+    })
+}
+
+/// Schedule, last notifications and last reminder result of a user.
+#[server]
+pub async fn query_admin_user(username: String) -> Result<AdminUser, ServerFnError> {
+    current_admin().await?;
+    let row = match query("SELECT * FROM users WHERE username = ?")
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(ServerFnError::new("Username not found")),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let (settings, tracked) = match (settings_from_row(&row), tracked_projects(&username).await) {
+        (Ok(s), Ok(t)) => (s, t),
+        _ => return Err(ServerFnError::new("Database error")),
+    };
+    let (events, evaluation) = match (
+        query("SELECT * FROM events WHERE username = ? AND kind = 'ntfy' ORDER BY id DESC LIMIT 10")
+            .bind(username.clone())
+            .fetch_all(&*POOL)
+            .await,
+        query("SELECT * FROM evaluations WHERE username = ? ORDER BY id DESC LIMIT 1")
+            .bind(username.clone())
+            .fetch_optional(&*POOL)
+            .await,
+    ) {
+        (Ok(n), Ok(e)) => (n, e),
+        _ => return Err(ServerFnError::new("Database error")),
+    };
+    let time = |r: &SqliteRow, column: &str| {
+        NaiveDateTime::parse_from_str(&r.get::<String, _>(column), "%Y-%m-%d %H:%M:%S%.f")
+    };
+    let notifications = events
+        .iter()
+        .map(|r| match time(r, "time") {
+            Ok(t) => Ok(Event {
+                time: t,
+                kind: r.get("kind"),
+                ok: r.get::<i64, _>("ok") != 0,
+                detail: r.get("detail"),
+            }),
+            Err(_) => Err(ServerFnError::new("Database error")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let evaluation = match evaluation {
+        Some(r) => match (time(&r, "time"), time(&r, "window_start")) {
+            (Ok(t), Ok(start)) => Some(Evaluation {
+                time: t,
+                day: r.get("day"),
+                window_start: start,
+                target: r.get("target"),
+                seconds: r.get("seconds"),
+            }),
+            _ => return Err(ServerFnError::new("Database error")),
+        },
+        None => None,
+    };
+    Ok(AdminUser {
+        email: row.get("email"),
+        role: Role::from_i64(row.get("role")),
+        disabled: row.get::<i64, _>("disabled") != 0,
+        username,
+        settings,
+        tracked,
+        notifications,
+        evaluation,
+    })
+}
+
+/// Stops or allows notifications and logins of an account.
+#[server]
+pub async fn set_user_disabled(username: String, disabled: bool) -> Result<(), ServerFnError> {
+    let admin = require_admin().await?;
+    if admin == username {
+        return Err(ServerFnError::new("You can't disable yourself"));
+    }
+    match set_disabled(&username, disabled).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ServerFnError::new("Username not found")),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_projects(projects: Vec<String>) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    save_projects(&username, &projects).await
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn save_projects(username: &str, projects: &[String]) -> Result<(), ServerFnError> {
+    let mut tx = match POOL.begin().await {
+        Ok(t) => t,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    if query("DELETE FROM projects WHERE username = ?")
+        .bind(username)
+        .execute(&mut *tx)
+        .await
+        .is_err()
+    {
+        return Err(ServerFnError::new("Database error"));
+    }
+    for project in projects {
+        if query("INSERT OR IGNORE INTO projects (username, name) VALUES (?, ?)")
+            .bind(username)
+            .bind(project)
+            .execute(&mut *tx)
+            .await
+            .is_err()
+        {
+            return Err(ServerFnError::new("Database error"));
+        }
+    }
+    if tx.commit().await.is_err() {
+        return Err(ServerFnError::new("Database error"));
+    }
+    if save_week_projects(username, week_of(Utc::now().naive_utc()), projects)
+        .await
+        .is_err()
+    {
+        return Err(ServerFnError::new("Database error"));
+    }
+    match create_checklists(username, projects).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn add_alias(
+    project: String,
+    kind: AliasKind,
+    pattern: String,
+) -> Result<Alias, ServerFnError> {
+    let username = require_user().await?;
+    let valid = match kind {
+        AliasKind::Exact => true,
+        AliasKind::Glob => glob_to_regex(&pattern).is_ok(),
+        AliasKind::Regex => regex::Regex::new(&pattern).is_ok(),
+    };
+    if !valid || pattern.is_empty() {
+        return Err(ServerFnError::new("Invalid pattern"));
+    }
+    match query("INSERT INTO aliases (username, project, kind, pattern) VALUES (?, ?, ?, ?)")
+        .bind(username)
+        .bind(project.clone())
+        .bind(kind.to_i64())
+        .bind(pattern.clone())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(r) => Ok(Alias {
+            id: r.last_insert_rowid(),
+            project,
+            kind,
+            pattern,
+        }),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn remove_alias(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM aliases WHERE id = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_times(times: Settings) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    save_settings(&username, &times).await
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn save_settings(username: &str, times: &Settings) -> Result<(), ServerFnError> {
+    let mut sql = "UPDATE users SET ".to_string();
+    for day in ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"] {
+        sql.push_str(&format!("{0} = ?, {0}_goal = ?, {0}_window = ?, ", day));
+    }
+    sql.push_str("utc_offset = ? WHERE username = ?");
+
+    let mut q = query(&sql);
+    for slot in times.slots() {
+        q = q.bind(slot.time).bind(slot.goal).bind(slot.window.to_i64());
+    }
+    match q.bind(times.offset).bind(username).execute(&*POOL).await {
+        Ok(_) => (),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+    // times without a timezone moved with the offset
+    match expand_calendars(username, times.offset).await {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_carry(mode: CarryMode, cap: i32) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("UPDATE users SET carry_mode = ?, bank_cap = ? WHERE username = ?")
+        .bind(mode.to_i64())
+        .bind(cap.max(0))
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_project_carry(carry: ProjectCarry) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    let days = match carry {
+        ProjectCarry::MostWorked(days) => days.max(1),
+        _ => 7,
+    };
+    match query("UPDATE users SET project_carry = ?, project_carry_days = ? WHERE username = ?")
+        .bind(carry.to_i64())
+        .bind(days)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Sets the long-term goal, `None` removes it.
+#[server]
+pub async fn set_long_goal(goal: Option<LongGoal>) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    save_long_goal(&username, goal).await
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn save_long_goal(
+    username: &str,
+    goal: Option<LongGoal>,
+) -> Result<(), ServerFnError> {
+    let (hours, project, start, end) = match goal {
+        Some(g) => {
+            if g.hours <= 0 || g.project.trim().is_empty() {
+                return Err(ServerFnError::new("Set the hours and a project"));
+            }
+            if g.end < g.start {
+                return Err(ServerFnError::new("The goal ends before it starts"));
+            }
+            (g.hours, g.project.trim().to_string(), g.start.to_string(), g.end.to_string())
+        }
+        None => (0, String::new(), String::new(), String::new()),
+    };
+    match query("UPDATE users SET goal_hours = ?, goal_project = ?, goal_start = ?, goal_end = ? WHERE username = ?")
+        .bind(hours)
+        .bind(project)
+        .bind(start)
+        .bind(end)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Sets the alerts before the submission deadline, in minutes.
+#[server]
+pub async fn set_deadline_alerts(alerts: Vec<i64>) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    if alerts.iter().any(|a| !(1..WEEK).contains(a)) {
+        return Err(ServerFnError::new("Alerts have to be within a week"));
+    }
+    match query("UPDATE users SET deadline_alerts = ? WHERE username = ?")
+        .bind(alerts.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(","))
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Confirms shipping this week, which stops the deadline alerts. The url is optional.
+#[server]
+pub async fn set_shipped(shipped: bool, url: String) -> Result<Option<Submission>, ServerFnError> {
+    let username = require_user().await?;
+    let url = url.trim().to_string();
+    if !url.is_empty() && !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(ServerFnError::new("The submission has to be a link"));
+    }
+    let week = week_of(Utc::now().naive_utc());
+    if shipped {
+        if save_submission(&username, week, &url).await.is_err() {
+            return Err(ServerFnError::new("Database error"));
+        }
+    } else if query("DELETE FROM submissions WHERE username = ? AND week = ?")
+        .bind(username.clone())
+        .bind(week.to_string())
+        .execute(&*POOL)
+        .await
+        .is_err()
+    {
+        return Err(ServerFnError::new("Database error"));
+    }
+    match submission(&username, week).await {
+        Ok(s) => Ok(s),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn add_milestone(milestone: Milestone) -> Result<Milestone, ServerFnError> {
+    let username = require_user().await?;
+    let name = milestone.name.trim().to_string();
+    if name.is_empty() || milestone.project.is_empty() {
+        return Err(ServerFnError::new("Set a name and a project"));
+    }
+    let target = milestone.target.filter(|t| *t > 0);
+    let mut offsets: Vec<i32> = milestone.offsets.into_iter().filter(|o| *o >= 0).collect();
+    offsets.sort_unstable_by(|a, b| b.cmp(a));
+    offsets.dedup();
+    match query("INSERT INTO milestones (username, project, name, target, due, offsets, token) VALUES (?, ?, ?, ?, ?, ?, ?)")
+        .bind(username)
+        .bind(milestone.project.clone())
+        .bind(name.clone())
+        .bind(target)
+        .bind(milestone.due.to_string())
+        .bind(offsets.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(","))
+        .bind(new_token())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(r) => Ok(Milestone {
+            id: r.last_insert_rowid(),
+            name,
+            target,
+            offsets,
+            done: false,
+            ..milestone
+        }),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_milestone_done(id: i64, done: bool) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("UPDATE milestones SET done = ? WHERE id = ? AND username = ?")
+        .bind(done)
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn remove_milestone(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM milestones WHERE id = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn add_checklist_item(
+    project: String,
+    name: String,
+) -> Result<ChecklistItem, ServerFnError> {
+    let username = require_user().await?;
+    let name = name.trim().to_string();
+    if name.is_empty() || project.is_empty() {
+        return Err(ServerFnError::new("Set a name and a project"));
+    }
+    match query("INSERT INTO checklist (username, project, name) VALUES (?, ?, ?)")
+        .bind(username)
+        .bind(project.clone())
+        .bind(name.clone())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(r) => Ok(ChecklistItem {
+            id: r.last_insert_rowid(),
+            project,
+            name,
+            done: false,
+        }),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_checklist_item_done(id: i64, done: bool) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("UPDATE checklist SET done = ? WHERE id = ? AND username = ?")
+        .bind(done)
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn remove_checklist_item(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM checklist WHERE id = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
 
-use crate::datatypes::{Data, Project, Settings};
+/// The journal of a day, projects without an entry get a draft from Hackatime.
+#[server]
+pub async fn query_journal(day: NaiveDate) -> Result<Journal, ServerFnError> {
+    let username = current_user().await?;
+    let row = match query("SELECT * FROM users WHERE username = ?")
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(ServerFnError::new("Username not found")),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let settings = match settings_from_row(&row) {
+        Ok(s) => s,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let (tracked, aliases, written) = match (
+        tracked_projects(&username).await,
+        aliases(&username).await,
+        journal(&username, day, day).await,
+    ) {
+        (Ok(t), Ok(a), Ok(w)) => (t, a, w),
+        _ => return Err(ServerFnError::new("Database error")),
+    };
 
-#[cfg(feature = "ssr")]
-use crate::db::POOL;
-#[cfg(feature = "ssr")]
-use sqlx::{query, Row};
+    let mut entries = written.clone();
+    for project in tracked.into_iter().filter(|t| !written.iter().any(|w| w.project == *t)) {
+        let text = match fetch_draft(&username, &project, day, settings.offset, &aliases).await {
+            Ok(t) => t,
+            Err(e) => return Err(ServerFnError::new(e)),
+        };
+        entries.push(JournalEntry { project, day, text });
+    }
+    entries.sort_by(|a, b| a.project.cmp(&b.project));
+
+    Ok(Journal {
+        entries,
+        prompt: row.get("journal_prompt"),
+    })
+}
 
 #[server]
-pub async fn set_project(username: String, project: String) -> Result<(), ServerFnError> {
-    match query("UPDATE users SET project = ? WHERE username = ?")
-        .bind(project)
+pub async fn save_journal_entry(entry: JournalEntry) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    if entry.project.is_empty() {
+        return Err(ServerFnError::new("Set a project"));
+    }
+    match query("INSERT INTO journal (username, project, day, text) VALUES (?, ?, ?, ?) ON CONFLICT (username, project, day) DO UPDATE SET text = excluded.text")
+        .bind(username)
+        .bind(entry.project)
+        .bind(entry.day.to_string())
+        .bind(entry.text)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Sets the local minute of the evening journal prompt, `None` turns it off.
+#[server]
+pub async fn set_journal_prompt(prompt: Option<i32>) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    if prompt.is_some_and(|p| !(0..24 * 60).contains(&p)) {
+        return Err(ServerFnError::new("Invalid time"));
+    }
+    match query("UPDATE users SET journal_prompt = ? WHERE username = ?")
+        .bind(prompt)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// The journal of the week starting on the monday `week` as Markdown.
+#[server]
+pub async fn export_journal(week: NaiveDate) -> Result<String, ServerFnError> {
+    let username = current_user().await?;
+    let week = week - Duration::days(week.weekday().num_days_from_monday() as i64);
+    match journal(&username, week, week + Duration::days(6)).await {
+        Ok(entries) => Ok(markdown(week, &entries)),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn query_calendar() -> Result<CalendarData, ServerFnError> {
+    let username = current_user().await?;
+    let vacations = match vacations(&username).await {
+        Ok(v) => v,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let overrides = match overrides(&username).await {
+        Ok(o) => o,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let calendars = match calendars(&username).await {
+        Ok(c) => c,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    Ok(CalendarData {
+        vacations,
+        overrides,
+        calendars,
+    })
+}
+
+/// Imports an iCalendar file, either its `content` or a `url` to fetch it from.
+#[server]
+pub async fn add_calendar(
+    name: String,
+    url: String,
+    content: String,
+) -> Result<CalendarFile, ServerFnError> {
+    let username = require_user().await?;
+    let url = url.trim().to_string();
+    let content = if url.is_empty() {
+        if content.len() > ical::MAX_CALENDAR_BYTES {
+            return Err(ServerFnError::new(format!(
+                "Calendars can be up to {} MB",
+                ical::MAX_CALENDAR_BYTES / 1024 / 1024
+            )));
+        }
+        if !ical::is_calendar(&content) {
+            return Err(ServerFnError::new("Not an iCalendar file"));
+        }
+        content
+    } else {
+        match ical::fetch(&url).await {
+            Ok(c) => c,
+            Err(e) => return Err(ServerFnError::new(e)),
+        }
+    };
+    let name = match name.trim() {
+        "" if url.is_empty() => "Calendar".to_string(),
+        "" => url.clone(),
+        n => n.to_string(),
+    };
+    let events = ical::parse(&content, 0).len() as i32;
+    let offset = match query("SELECT utc_offset FROM users WHERE username = ?")
+        .bind(username.clone())
+        .fetch_one(&*POOL)
+        .await
+    {
+        Ok(r) => r.get::<i32, _>("utc_offset"),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let id = match query("INSERT INTO calendars (username, name, url, content) VALUES (?, ?, ?, ?)")
+        .bind(username.clone())
+        .bind(name.clone())
+        .bind(url.clone())
+        .bind(content.clone())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(r) => r.last_insert_rowid(),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    match save_occurrences(id, &username, &content, offset).await {
+        Ok(_) => Ok(CalendarFile {
+            id,
+            name,
+            url,
+            events,
+        }),
+        Err(_) => Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn remove_calendar(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM calendars WHERE id = ? AND username = ?")
+        .bind(id)
+        .bind(username.clone())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => (),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+    match query("DELETE FROM calendar_occurrences WHERE calendar = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn add_vacation(start: NaiveDate, end: NaiveDate) -> Result<Vacation, ServerFnError> {
+    let username = require_user().await?;
+    if end < start {
+        return Err(ServerFnError::new("The vacation ends before it starts"));
+    }
+    match query("INSERT INTO vacations (username, start, end) VALUES (?, ?, ?)")
+        .bind(username)
+        .bind(start.to_string())
+        .bind(end.to_string())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(r) => Ok(Vacation {
+            id: r.last_insert_rowid(),
+            start,
+            end,
+        }),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn remove_vacation(id: i64) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM vacations WHERE id = ? AND username = ?")
+        .bind(id)
+        .bind(username)
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+#[server]
+pub async fn set_override(day: Override) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    if !(0..24 * 60).contains(&day.time) || day.goal < 0 {
+        return Err(ServerFnError::new("Invalid time or goal"));
+    }
+    match query("INSERT OR REPLACE INTO overrides (username, date, time, goal) VALUES (?, ?, ?, ?)")
         .bind(username)
+        .bind(day.date.to_string())
+        .bind(day.time)
+        .bind(day.goal)
         .execute(&*POOL)
         .await
     {
@@ -24,25 +1132,57 @@ pub async fn set_project(username: String, project: String) -> Result<(), Server
 }
 
 #[server]
-pub async fn set_times(username: String, times: Settings) -> Result<(), ServerFnError> {
-    match query("UPDATE users SET monday = ?, tuesday = ?, wednesday = ?, thursday = ?, friday = ?, saturday = ?, sunday = ? WHERE username = ?")
-        .bind(times.monday.0)
-        .bind(times.tuesday.0)
-        .bind(times.wednesday.0)
-        .bind(times.thursday.0)
-        .bind(times.friday.0)
-        .bind(times.saturday.0)
-        .bind(times.sunday.0)
+pub async fn remove_override(date: NaiveDate) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    match query("DELETE FROM overrides WHERE username = ? AND date = ?")
         .bind(username)
-        .execute(&*POOL).await {
+        .bind(date.to_string())
+        .execute(&*POOL)
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Sets where notifications go.
+#[server]
+pub async fn set_channels(channels: Channels) -> Result<(), ServerFnError> {
+    let username = require_user().await?;
+    save_channels(&username, channels).await
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn save_channels(username: &str, channels: Channels) -> Result<(), ServerFnError> {
+    let topic = channels.ntfy_topic.trim();
+    if let Some(e) = topic_error(username, topic) {
+        return Err(ServerFnError::new(e));
+    }
+    // whoever read a code on the public default topic could lock the owner out
+    match has_secret_topic(username).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return Err(ServerFnError::new(
+                "This account has no secret topic yet, ask an admin to set one",
+            ))
+        }
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+    match set_ntfy_topic(username, topic).await {
         Ok(_) => Ok(()),
         Err(_) => return Err(ServerFnError::new("Database error")),
     }
 }
 
 #[server]
-pub async fn query_data(username: String) -> Result<Data, ServerFnError> {
-    let (username, projects) = query_projects(username.clone()).await?;
+pub async fn query_data() -> Result<Data, ServerFnError> {
+    let username = current_user().await?;
+    data_for(username).await
+}
+
+#[cfg(feature = "ssr")]
+async fn data_for(username: String) -> Result<Data, ServerFnError> {
+    let (username, projects, seconds) = query_projects(username.clone()).await?;
     let row = match query("SELECT * FROM users WHERE username = ?")
         .bind(username.clone())
         .fetch_one(&*POOL)
@@ -50,113 +1190,410 @@ pub async fn query_data(username: String) -> Result<Data, ServerFnError> {
     {
         Ok(r) => r,
         Err(sqlx::Error::RowNotFound) => {
-            match query("INSERT INTO users (username, project, monday, monday_goal, tuesday, tuesday_goal, wednesday, wednesday_goal, thursday, thursday_goal, friday, friday_goal, saturday, saturday_goal, sunday, sunday_goal) VALUES (?, '', 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 3, 0, 3)").bind(username.clone()).execute(&*POOL).await {
-                Ok(_) => return Ok(Data { username, projects, primary: "".to_string(), settings: Settings { monday: (0, 1), tuesday: (0, 1), wednesday: (0, 1), thursday: (0, 1), friday: (0, 1), saturday: (0, 3), sunday: (0, 3) } }),
+            match create_user(&username).await {
+                Ok(feed_token) => return Ok(Data {
+                    username,
+                    projects,
+                    tracked: Vec::new(),
+                    aliases: Vec::new(),
+                    settings: Settings {
+                        monday: Slot { time: 0, goal: 1, window: Window::PreviousSlot },
+                        tuesday: Slot { time: 0, goal: 1, window: Window::PreviousSlot },
+                        wednesday: Slot { time: 0, goal: 1, window: Window::PreviousSlot },
+                        thursday: Slot { time: 0, goal: 1, window: Window::PreviousSlot },
+                        friday: Slot { time: 0, goal: 1, window: Window::PreviousSlot },
+                        saturday: Slot { time: 0, goal: 3, window: Window::PreviousSlot },
+                        sunday: Slot { time: 0, goal: 3, window: Window::WeekToDate },
+                        offset: 0,
+                    },
+                    carry: Carry { mode: CarryMode::Off, cap: 0, balance: 0, bank: 0 },
+                    project_carry: ProjectCarry::Clear,
+                    feed_token,
+                    pace: None,
+                    long_goal: None,
+                    milestones: Vec::new(),
+                    deadline_alerts: vec![24 * 60, 6 * 60, 60, 15],
+                    submission: None,
+                    checklist: Vec::new(),
+                    channels: Channels { ntfy_topic: String::new() },
+                }),
                 Err(_) => return Err(ServerFnError::new("Database error")),
             }
         },
         Err(_) => return Err(ServerFnError::new("Database error")),
     };
-    let primary: String = match row.try_get("project") {
-        Ok(p) => p,
-        Err(_) => "".to_string(),
+    let tracked = match tracked_projects(&username).await {
+        Ok(t) => t,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let aliases = match aliases(&username).await {
+        Ok(a) => a,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let settings = match settings_from_row(&row) {
+        Ok(s) => s,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let carry = match carry_from_row(&row) {
+        Ok(c) => c,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let project_carry = match project_carry_from_row(&row) {
+        Ok(c) => c,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let long_goal = match long_goal_from_row(&row) {
+        Ok(g) => g,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let milestones = match milestones(&username).await {
+        Ok(m) => m,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let checklist = match checklist(&username, &tracked).await {
+        Ok(c) => c,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let deadline_alerts = match deadline_alerts_from_row(&row) {
+        Ok(a) => a,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let submission = match submission(&username, week_of(Utc::now().naive_utc())).await {
+        Ok(s) => s,
+        Err(_) => return Err(ServerFnError::new("Database error")),
     };
+    let mut feed_token: String = row.get("feed_token");
+    if feed_token.is_empty() {
+        // users from before the feed existed
+        feed_token = new_token();
+        if query("UPDATE users SET feed_token = ? WHERE username = ?")
+            .bind(feed_token.clone())
+            .bind(username.clone())
+            .execute(&*POOL)
+            .await
+            .is_err()
+        {
+            return Err(ServerFnError::new("Database error"));
+        }
+    }
+
+    let week_seconds = seconds
+        .iter()
+        .filter(|(name, _)| resolve(name, &tracked, &aliases).is_some())
+        .map(|p| p.1)
+        .sum();
+    let now = Utc::now().naive_utc();
+    let monday = now.date().week(Weekday::Mon).first_day().and_hms_opt(0, 0, 0).unwrap();
+    let pace = settings.projection((now - monday).num_minutes(), week_seconds);
 
     Ok(Data {
         username,
         projects,
-        primary,
-        settings: Settings {
-            monday: (match row.try_get("monday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("monday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-            tuesday: (match row.try_get("tuesday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("tuesday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-            wednesday: (match row.try_get("wednesday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("wednesday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-            thursday: (match row.try_get("thursday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("thursday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-            friday: (match row.try_get("friday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("friday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-            saturday: (match row.try_get("saturday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("saturday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-            sunday: (match row.try_get("sunday") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }, match row.try_get("sunday_goal") {
-                Ok(p) => p,
-                Err(_) => return Err(ServerFnError::new("Database error")),
-            }),
-        },
+        tracked,
+        aliases,
+        settings,
+        carry,
+        project_carry,
+        feed_token,
+        pace,
+        long_goal,
+        milestones,
+        deadline_alerts,
+        submission,
+        checklist,
+        channels: Channels { ntfy_topic: row.get("ntfy_topic") },
     })
 }
 
-async fn query_projects(username: String) -> Result<(String, Vec<Project>), ServerFnError> {
+#[server]
+pub async fn query_stats() -> Result<Stats, ServerFnError> {
+    let username = current_user().await?;
+    let row = match query("SELECT * FROM users WHERE username = ?")
+        .bind(username.clone())
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(ServerFnError::new("Username not found")),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let (slots, long_goal) = match (settings_from_row(&row), long_goal_from_row(&row)) {
+        (Ok(s), Ok(g)) => (s.slots(), g),
+        _ => return Err(ServerFnError::new("Database error")),
+    };
+    let (tracked, aliases, snapshots, vacations, overrides) = match (
+        tracked_projects(&username).await,
+        aliases(&username).await,
+        snapshots(&username).await,
+        vacations(&username).await,
+        overrides(&username).await,
+    ) {
+        (Ok(t), Ok(a), Ok(s), Ok(v), Ok(o)) => (t, a, s, v, o),
+        _ => return Err(ServerFnError::new("Database error")),
+    };
+    // vacations have no goal and overrides replace the one of the schedule
+    let goal_on = |date: NaiveDate| {
+        if vacations.iter().any(|v| v.start <= date && date <= v.end) {
+            return None;
+        }
+        let hours = match overrides.iter().find(|o| o.date == date) {
+            Some(o) => o.goal,
+            None => slots[date.weekday().num_days_from_monday() as usize].goal,
+        };
+        Some(hours as i64 * 60 * 60)
+    };
+
     let today = Utc::now().date_naive();
-    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
-    let client = Client::new();
-    let res = match client
-        .get(format!(
-            "https://hackatime.hackclub.com/api/v1/users/{}/stats?features=projects&start_date={}",
-            username,
-            week_start.and_hms_opt(4, 0, 0).unwrap().and_utc().to_rfc3339()
-        ))
-        .send()
+    let monday = |day: NaiveDate| day - Duration::days(day.weekday().num_days_from_monday() as i64);
+    let this_week = monday(today);
+
+    let days = (0..7)
+        .map(|i| {
+            let date = this_week + Duration::days(i);
+            Bar {
+                label: date.format("%a").to_string(),
+                seconds: snapshots
+                    .iter()
+                    .filter(|s| s.1 == date && resolve(&s.0, &tracked, &aliases).is_some())
+                    .map(|s| s.2)
+                    .sum(),
+                goal: goal_on(date),
+            }
+        })
+        .collect();
+
+    // weeks end at the submission deadline, snapshots are whole days so a monday is in the new one
+    let mut weeks: Vec<Bar> = Vec::new();
+    if let Some(first) = snapshots.first() {
+        let mut week = week_of(first.1.and_hms_opt(12, 0, 0).unwrap());
+        while week <= week_of(Utc::now().naive_utc()) {
+            let end = week + Duration::days(7);
+            weeks.push(Bar {
+                label: week.format("%d.%m").to_string(),
+                seconds: snapshots
+                    .iter()
+                    .filter(|s| {
+                        week <= s.1 && s.1 < end && resolve(&s.0, &tracked, &aliases).is_some()
+                    })
+                    .map(|s| s.2)
+                    .sum(),
+                goal: Some(
+                    (0..7)
+                        .filter_map(|i| goal_on(week + Duration::days(i)))
+                        .sum(),
+                ),
+            });
+            week = end;
+        }
+    }
+
+    let mut projects: Vec<Bar> = Vec::new();
+    for (project, _, seconds) in snapshots.iter().filter(|s| s.1 >= this_week) {
+        match projects.iter_mut().find(|p| p.label == *project) {
+            Some(p) => p.seconds += seconds,
+            None => projects.push(Bar {
+                label: project.clone(),
+                seconds: *seconds,
+                goal: None,
+            }),
+        }
+    }
+    projects.retain(|p| p.seconds > 0);
+    projects.sort_by(|a, b| b.seconds.cmp(&a.seconds));
+
+    let goal = long_goal.map(|g| g.status(&daily_totals(&snapshots, &g.project, &aliases), today));
+
+    Ok(Stats {
+        days,
+        weeks,
+        projects,
+        goal,
+    })
+}
+
+/// A week's projects, what was tracked and how the reminders went. `week` is the weeks monday.
+#[server]
+pub async fn query_week(week: NaiveDate) -> Result<WeekData, ServerFnError> {
+    let username = current_user().await?;
+    week_for(&username, week).await
+}
+
+/// Projects and reminder results of the week `week` is in.
+#[cfg(feature = "ssr")]
+pub(crate) async fn week_for(username: &str, week: NaiveDate) -> Result<WeekData, ServerFnError> {
+    let week = week_of(week.and_hms_opt(12, 0, 0).unwrap());
+    let start = week.and_hms_opt(0, 0, 0).unwrap() + Duration::minutes(WEEK_START);
+    let end = start + Duration::weeks(1);
+
+    let projects = match stats(username, "projects", start.and_utc(), Some(end.and_utc())).await {
+        Ok(d) => match parse_projects(d) {
+            Some((_, p)) => p,
+            None => return Err(ServerFnError::new("Failed to parse response data")),
+        },
+        // fall back to the stored history, it's counted in whole days
+        Err(e) => {
+            let snapshots = match snapshots(username).await {
+                Ok(s) => s,
+                Err(_) => return Err(ServerFnError::new("Database error")),
+            };
+            let mut totals: Vec<(String, i64)> = Vec::new();
+            for (project, _, seconds) in snapshots.iter().filter(|s| week <= s.1 && s.1 < end.date()) {
+                match totals.iter_mut().find(|t| t.0 == *project) {
+                    Some(t) => t.1 += seconds,
+                    None => totals.push((project.clone(), *seconds)),
+                }
+            }
+            if totals.is_empty() {
+                return Err(ServerFnError::new(e));
+            }
+            totals.sort_by(|a, b| b.1.cmp(&a.1));
+            totals
+                .into_iter()
+                .map(|(name, sec)| Project {
+                    name,
+                    time: format!("{}h {}m", sec / 3600, sec % 3600 / 60),
+                })
+                .collect()
+        }
+    };
+
+    let tracked = if week == week_of(Utc::now().naive_utc()) {
+        tracked_projects(username).await
+    } else {
+        week_projects(username, week).await
+    };
+    let tracked = match tracked {
+        Ok(t) => t,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+
+    let rows = match query("SELECT * FROM evaluations WHERE username = ? AND time >= ? AND time < ? ORDER BY time")
+        .bind(username)
+        .bind(start.to_string())
+        .bind(end.to_string())
+        .fetch_all(&*POOL)
         .await
     {
-        Ok(res) => res,
-        Err(e) => return Err(ServerFnError::new(e)),
+        Ok(r) => r,
+        Err(_) => return Err(ServerFnError::new("Database error")),
     };
-    if res.status() == StatusCode::NOT_FOUND {
-        return Err(ServerFnError::new("Username not found"));
-    } else if !res.status().is_success() {
-        return Err(ServerFnError::new(
-            res.status()
-                .canonical_reason()
-                .unwrap_or_else(|| "Unknown error"),
-        ));
+    let mut days: Vec<DayResult> = Vec::new();
+    for r in rows {
+        let day = DayResult {
+            day: r.get("day"),
+            target: r.get("target"),
+            seconds: r.get("seconds"),
+        };
+        // the last reminder of a day counts
+        days.retain(|d| d.day != day.day);
+        days.push(day);
     }
+    days.sort_by_key(|d| d.day);
+
+    let submission = match submission(username, week).await {
+        Ok(s) => s,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+
+    Ok(WeekData {
+        start: week,
+        projects,
+        tracked,
+        days,
+        submission,
+    })
+}
 
-    let data: Value = match res.json().await {
+/// This weeks projects for display and every projects seconds.
+#[cfg(feature = "ssr")]
+async fn query_projects(
+    username: String,
+) -> Result<(String, Vec<Project>, Vec<(String, i64)>), ServerFnError> {
+    let today = Utc::now().date_naive();
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let data = match stats(
+        &username,
+        "projects",
+        week_start.and_hms_opt(4, 0, 0).unwrap().and_utc(),
+        None,
+    )
+    .await
+    {
         Ok(d) => d,
-        Err(_) => return Err(ServerFnError::new("Failed to deserialize response data")),
+        Err(e) => return Err(ServerFnError::new(e)),
     };
 
+    let seconds = all_projects(&data);
     match parse_projects(data) {
-        Some(d) => Ok(d),
+        Some((username, projects)) => Ok((username, projects, seconds)),
         None => Err(ServerFnError::new("Failed to parse response data")),
     }
 }
 
+/// Hackatime projects of this week and the tracked ones.
+#[cfg(feature = "ssr")]
+pub(crate) async fn projects_for(username: &str) -> Result<Projects, ServerFnError> {
+    let (_, projects, _) = query_projects(username.to_string()).await?;
+    match tracked_projects(username).await {
+        Ok(tracked) => Ok(Projects { tracked, projects }),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    }
+}
+
+/// Time on the tracked projects this week and today.
+#[cfg(feature = "ssr")]
+pub(crate) async fn progress_for(username: &str) -> Result<Progress, ServerFnError> {
+    let row = match query("SELECT * FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&*POOL)
+        .await
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => return Err(ServerFnError::new("Save your settings first")),
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let (settings, tracked, aliases) =
+        match (settings_from_row(&row), tracked_projects(username).await, aliases(username).await) {
+            (Ok(s), Ok(t), Ok(a)) => (s, t, a),
+            _ => return Err(ServerFnError::new("Database error")),
+        };
+    let now = Utc::now().naive_utc();
+    let tracked_seconds = |data: &Value| -> i64 {
+        all_projects(data)
+            .iter()
+            .filter(|(name, _)| resolve(name, &tracked, &aliases).is_some())
+            .map(|p| p.1)
+            .sum()
+    };
+
+    let week_start = week_of(now).and_hms_opt(0, 0, 0).unwrap() + Duration::minutes(WEEK_START);
+    let week = match stats(username, "projects", week_start.and_utc(), None).await {
+        Ok(d) => tracked_seconds(&d),
+        Err(e) => return Err(ServerFnError::new(e)),
+    };
+    let today = settings.local_date(now);
+    let midnight = today.and_hms_opt(0, 0, 0).unwrap() - Duration::seconds(settings.offset as i64);
+    let today_seconds = match stats(username, "projects", midnight.and_utc(), None).await {
+        Ok(d) => tracked_seconds(&d),
+        Err(e) => return Err(ServerFnError::new(e)),
+    };
+    let shipped = match shipped(username, week_of(now)).await {
+        Ok(s) => s,
+        Err(_) => return Err(ServerFnError::new("Database error")),
+    };
+    let monday = now.date().week(Weekday::Mon).first_day().and_hms_opt(0, 0, 0).unwrap();
+
+    Ok(Progress {
+        week,
+        today: today_seconds,
+        today_goal: settings.slots()[today.weekday().num_days_from_monday() as usize].goal,
+        pace: settings.projection((now - monday).num_minutes(), week),
+        shipped,
+        tracked,
+    })
+}
+
+#[cfg(feature = "ssr")]
 fn parse_projects(data: Value) -> Option<(String, Vec<Project>)> {
     let mut out = Vec::new();
 
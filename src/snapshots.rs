@@ -0,0 +1,114 @@
+#![cfg(feature = "ssr")]
+
+use chrono::{Days, Duration, NaiveDate, Utc};
+use sqlx::{query, Row};
+use std::{
+    env,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{db::POOL, hackatime::stats, projects::all_projects};
+
+/// How far back history is filled in for new users if `EVENT_START` isn't set.
+const DEFAULT_BACKFILL: u64 = 8 * 7;
+
+/// Runs that fail before a users history is filled in, after that only new days are recorded.
+const BACKFILL_TRIES: i64 = 3;
+
+/// Whether a run is going on, a slow one can take longer than the hour until the next.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Records every users per project time of today and yesterday (UTC), users without history get
+/// every day since the start of the event.
+pub async fn record_snapshots() {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        eprintln!("Snapshots of the last run are still being recorded");
+        return;
+    }
+    record_all().await;
+    RUNNING.store(false, Ordering::SeqCst);
+}
+
+async fn record_all() {
+    let rows =
+        match query("SELECT username, backfilled, backfill_failures FROM users WHERE disabled = 0")
+            .fetch_all(&*POOL)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+    let today = Utc::now().date_naive();
+    for i in rows {
+        let username: String = i.get("username");
+        let backfilled: bool = i.get("backfilled");
+        // yesterday is fetched again since it was still going on during the last run
+        let first = if backfilled {
+            today - Days::new(1)
+        } else {
+            event_start(today)
+        };
+
+        let mut complete = true;
+        for day in first.iter_days().take_while(|d| *d <= today) {
+            if let Err(e) = record_day(&username, day).await {
+                eprintln!("{}", e);
+                complete = false;
+                // the other days would most likely fail the same way
+                break;
+            }
+        }
+        if backfilled {
+            continue;
+        }
+        let failures = i.get::<i64, _>("backfill_failures") + !complete as i64;
+        if failures >= BACKFILL_TRIES {
+            eprintln!("Giving up on the history of {}", username);
+        }
+        if let Err(e) =
+            query("UPDATE users SET backfilled = ?, backfill_failures = ? WHERE username = ?")
+                .bind(complete || failures >= BACKFILL_TRIES)
+                .bind(failures)
+                .bind(username)
+                .execute(&*POOL)
+                .await
+        {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+async fn record_day(username: &str, day: NaiveDate) -> Result<(), String> {
+    let start = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let data = stats(username, "projects", start, Some(start + Duration::days(1))).await?;
+    for (project, seconds) in all_projects(&data) {
+        if let Err(e) = query(
+            "INSERT OR REPLACE INTO snapshots (username, project, day, seconds) VALUES (?, ?, ?, ?)",
+        )
+        .bind(username)
+        .bind(project)
+        .bind(day.to_string())
+        .bind(seconds)
+        .execute(&*POOL)
+        .await
+        {
+            return Err(e.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// `EVENT_START` as YYYY-MM-DD, a few weeks back otherwise.
+fn event_start(today: NaiveDate) -> NaiveDate {
+    match env::var("EVENT_START").map(|s| s.parse::<NaiveDate>()) {
+        Ok(Ok(d)) => d.min(today),
+        Ok(Err(_)) => {
+            eprintln!("EVENT_START should look like 2025-09-01");
+            today - Days::new(DEFAULT_BACKFILL)
+        }
+        Err(_) => today - Days::new(DEFAULT_BACKFILL),
+    }
+}